@@ -0,0 +1,860 @@
+//! `#[derive(ReprC)]` for the `ffi-trait-poc` crate.
+//!
+//! For a struct `Xxx` with named fields this emits the companion `#[repr(C)] XxxFfi` struct, the
+//! `CRepr`, `FromReprC` and `IntoReprC` impls converting between the two and its `CHeader`
+//! description. Like every C representation, `XxxFfi` has no `Drop`; it is released through
+//! `FromReprC`, `IntoReprC::release_c` or an `OwnedFfi`.
+//! The error type of the conversion must be given as `#[repr_c(error = SomeError)]`; every
+//! field's own `CRepr::Error` must convert into it via `From`.
+//!
+//! Enums whose variants carry data become a tagged union `XxxFfi { tag: u32, payload:
+//! XxxFfiPayload }`, the payload being a `#[repr(C)]` union of one `XxxFfi<Variant>` struct per
+//! variant with data and `tag` the index of the variant. Releasing `XxxFfi` releases only the
+//! active variant. Enums without any data are represented by their discriminant as a `u32`. In
+//! both cases the error type must also implement `From<InvalidTag>`; for enums without data it
+//! defaults to `InvalidTag` itself.
+//!
+//! Both also get a `FromReprCBorrowed` impl whose view `XxxView<'a>` mirrors `Xxx` with the
+//! views of its fields; enums without data are their own view.
+//!
+//! The error type must implement `ErrorPath`: errors from fields are marked with the field's name
+//! after being converted into it, preceded by the variant's name in snake case for enums, as in
+//! `circle.radius`.
+//!
+//! Types that may be recursive count against `ffi_trait_poc::boxed::max_depth` when copied or
+//! viewed, so their error type must also implement `From<NestingError>`, and their Ffi structs
+//! end with a zero-sized `_recursive` marker. These are the types with a field mentioning the type
+//! itself, like `Vec<Node>` in `Node`, and those with a `Box`, `Vec`, map or set of anything but
+//! primitives and strings: every cycle of types, `A` holding `Vec<B>` and `B` holding
+//! `Option<Box<A>>` say, passes through such a field. Types recursing through a container hidden
+//! behind some other name must be marked `#[repr_c(recursive)]`. A field like `Box<Node>` fails
+//! with `BoxError<NodeError>`, which `BoxError::into_inner` turns back into the type's own error.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DataEnum, DeriveInput, Error, Expr, ExprLit, Fields, Ident, Lit,
+    Member, Type, Visibility,
+};
+
+#[proc_macro_derive(ReprC, attributes(repr_c))]
+pub fn derive_repr_c(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+struct FieldInfo {
+    /// Name of the field in the Ffi struct: the Rust name, or `_0`, `_1`, .. for tuple fields.
+    ident: Ident,
+    /// How the field is named in the Rust type.
+    member: Member,
+    vis: Visibility,
+    ty: Type,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "#[derive(ReprC)] does not support generic types",
+        ));
+    }
+
+    match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(_) => {
+                let attrs = repr_c_attrs(&input)?;
+                let error_ty = attrs
+                    .error
+                    .ok_or_else(|| missing_error_type(&input.ident))?;
+                let nesting = nesting(&input, attrs.recursive);
+                expand_struct(&input, &error_ty, &nesting, &fields(&data.fields))
+            }
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                "#[derive(ReprC)] only supports structs with named fields",
+            )),
+        },
+        Data::Enum(ref data) => {
+            if data.variants.iter().all(|v| v.fields.is_empty()) {
+                expand_c_like_enum(&input, data)
+            } else {
+                let attrs = repr_c_attrs(&input)?;
+                let error_ty = attrs
+                    .error
+                    .ok_or_else(|| missing_error_type(&input.ident))?;
+                let nesting = nesting(&input, attrs.recursive);
+                expand_tagged_enum(&input, &error_ty, &nesting, data)
+            }
+        }
+        Data::Union(_) => Err(Error::new_spanned(
+            &input.ident,
+            "#[derive(ReprC)] does not support unions",
+        )),
+    }
+}
+
+fn expand_struct(
+    input: &DeriveInput,
+    error_ty: &Type,
+    nesting: &Nesting,
+    fields: &[FieldInfo],
+) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let ffi_name = format_ident!("{}Ffi", name);
+    let vis = &input.vis;
+
+    let view_name = format_ident!("{}View", name);
+    let Nesting {
+        ref guard,
+        ref marker,
+        ref marker_init,
+    } = *nesting;
+
+    let c_repr = quote!(::ffi_trait_poc::CRepr);
+    let from_c = quote!(::ffi_trait_poc::FromReprC);
+    let into_c = quote!(::ffi_trait_poc::IntoReprC);
+    let from_borrowed = quote!(::ffi_trait_poc::FromReprCBorrowed);
+    let c_header = quote!(::ffi_trait_poc::CHeader);
+    let ffi_name_str = ffi_name.to_string();
+
+    let mut ffi_fields = Vec::new();
+    let mut owned = Vec::new();
+    let mut owned_build = Vec::new();
+    let mut cloned = Vec::new();
+    let mut convert = Vec::new();
+    let mut build = Vec::new();
+    let mut c_deps = Vec::new();
+    let mut c_fields = Vec::new();
+    let mut view_fields = Vec::new();
+    let mut borrowed = Vec::new();
+    let mut release = Vec::new();
+
+    for f in fields {
+        let FieldInfo {
+            ref ident,
+            ref vis,
+            ref ty,
+            ..
+        } = *f;
+
+        ffi_fields.push(quote!(#vis #ident: <#ty as #c_repr>::C));
+        let binding = format_ident!("__{}", ident);
+        let in_field = in_field(error_ty, None, &f.member);
+        owned.push(quote! {
+            let #binding = <#ty as #from_c>::from_repr_c_owned(&mut ffi.#ident)#in_field;
+        });
+        owned_build.push(quote!(#ident: #binding?));
+        cloned.push(quote! {
+            #ident: <#ty as #from_c>::from_repr_c_cloned(&ffi.#ident)#in_field?
+        });
+        convert.push(quote! {
+            let #binding = ::ffi_trait_poc::OwnedFfi::<#ty>::new(self.#ident)#in_field?;
+        });
+        build.push(quote!(#ident: #binding.into_c()));
+        release.push(quote!(<#ty as #into_c>::release_c(unsafe { &mut (*c).#ident });));
+        view_fields.push(quote!(#vis #ident: <#ty as #from_borrowed>::View<'a>));
+        borrowed.push(quote! {
+            #ident: <#ty as #from_borrowed>::from_repr_c_borrowed(&c.#ident)#in_field?
+        });
+        c_deps.push(quote!(<#ty as #c_header>::declare(header);));
+        c_fields.push(quote!(<#ty as #c_header>::c_decl(stringify!(#ident))));
+    }
+
+    // Without fields the view would not use its lifetime.
+    let (view_marker, view_marker_init) = if fields.is_empty() {
+        (
+            quote!(_marker: ::std::marker::PhantomData<&'a ()>,),
+            quote!(_marker: ::std::marker::PhantomData,),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    Ok(quote! {
+        #[repr(C)]
+        #[derive(Debug)]
+        #vis struct #ffi_name {
+            #(#ffi_fields,)*
+            #marker
+        }
+
+        impl #c_repr for #name {
+            type C = #ffi_name;
+            type Error = #error_ty;
+        }
+
+        impl #from_c for #name {
+            unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                let ffi = unsafe { &mut *c };
+                // Take every field before returning an error so that none is left unreleased.
+                #(#owned)*
+                Ok(#name {
+                    #(#owned_build,)*
+                })
+            }
+            unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                #guard
+                let ffi = unsafe { &*c };
+                Ok(#name {
+                    #(#cloned,)*
+                })
+            }
+        }
+
+        impl #into_c for #name {
+            fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                #(#convert)*
+                Ok(#ffi_name {
+                    #(#build,)*
+                    #marker_init
+                })
+            }
+            unsafe fn release_c(c: *mut Self::C) {
+                #(#release)*
+            }
+        }
+
+        #[derive(Debug)]
+        #vis struct #view_name<'a> {
+            #(#view_fields,)*
+            #view_marker
+        }
+
+        impl #from_borrowed for #name {
+            type View<'a> = #view_name<'a>;
+            type SliceView<'a> = Vec<#view_name<'a>>;
+
+            unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+                #guard
+                Ok(#view_name {
+                    #(#borrowed,)*
+                    #view_marker_init
+                })
+            }
+            unsafe fn slice_from_repr_c_borrowed<'a>(
+                c: &'a ::ffi_trait_poc::FfiVec<Self::C>,
+            ) -> Result<Self::SliceView<'a>, Self::Error> {
+                ::ffi_trait_poc::vec::borrowed_each::<Self>(c)
+            }
+        }
+
+        impl ::ffi_trait_poc::TaggedOption for #ffi_name {}
+
+        impl #c_header for #name {
+            fn c_decl(name: &str) -> String {
+                format!("{} {}", #ffi_name_str, name)
+            }
+            fn c_type_name() -> String {
+                #ffi_name_str.to_owned()
+            }
+            fn declare(header: &mut ::ffi_trait_poc::Header) {
+                if header.begin_struct(#ffi_name_str) {
+                    #(#c_deps)*
+                    header.add_struct(#ffi_name_str, vec![#(#c_fields),*]);
+                }
+            }
+        }
+    })
+}
+
+fn expand_c_like_enum(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let ffi_name_str = format!("{}Ffi", name);
+    let error_ty = match repr_c_attrs(input)?.error {
+        Some(ty) => quote!(#ty),
+        None => quote!(::ffi_trait_poc::InvalidTag),
+    };
+
+    let c_repr = quote!(::ffi_trait_poc::CRepr);
+    let from_c = quote!(::ffi_trait_poc::FromReprC);
+    let into_c = quote!(::ffi_trait_poc::IntoReprC);
+    let c_header = quote!(::ffi_trait_poc::CHeader);
+
+    // Same numbering as Rust: explicit discriminants, counting up from the previous one otherwise.
+    let mut tags = Vec::new();
+    let mut next = 0u32;
+    for variant in &data.variants {
+        let tag = match variant.discriminant {
+            Some((_, ref expr)) => discriminant(expr)?,
+            None => next,
+        };
+        next = tag.wrapping_add(1);
+        tags.push(Literal::u32_unsuffixed(tag));
+    }
+
+    let variants = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+    let variant_strs = variants.iter().map(|v| v.to_string());
+
+    Ok(quote! {
+        impl #c_repr for #name {
+            type C = u32;
+            type Error = #error_ty;
+        }
+
+        impl #from_c for #name {
+            unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                <Self as #from_c>::from_repr_c_cloned(c)
+            }
+            unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                match unsafe { *c } {
+                    #(#tags => Ok(#name::#variants),)*
+                    tag => Err(From::from(::ffi_trait_poc::InvalidTag {
+                        type_name: #name_str,
+                        tag: tag,
+                        path: ::ffi_trait_poc::FieldPath::default(),
+                    })),
+                }
+            }
+        }
+
+        impl #into_c for #name {
+            fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                Ok(match self {
+                    #(#name::#variants => #tags,)*
+                })
+            }
+            unsafe fn release_c(_c: *mut Self::C) {}
+        }
+
+        impl ::ffi_trait_poc::FromReprCBorrowed for #name {
+            type View<'a> = #name;
+            type SliceView<'a> = Vec<#name>;
+
+            unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+                <Self as #from_c>::from_repr_c_cloned(c)
+            }
+            unsafe fn slice_from_repr_c_borrowed<'a>(
+                c: &'a ::ffi_trait_poc::FfiVec<Self::C>,
+            ) -> Result<Self::SliceView<'a>, Self::Error> {
+                ::ffi_trait_poc::vec::borrowed_each::<Self>(c)
+            }
+        }
+
+        impl #c_header for #name {
+            fn c_decl(name: &str) -> String {
+                format!("{} {}", #ffi_name_str, name)
+            }
+            fn c_type_name() -> String {
+                #ffi_name_str.to_owned()
+            }
+            fn declare(header: &mut ::ffi_trait_poc::Header) {
+                if header.begin_struct(#ffi_name_str) {
+                    header.add_enum(#ffi_name_str, vec![#((#variant_strs.to_owned(), #tags)),*]);
+                }
+            }
+        }
+    })
+}
+
+fn expand_tagged_enum(
+    input: &DeriveInput,
+    error_ty: &Type,
+    nesting: &Nesting,
+    data: &DataEnum,
+) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let vis = &input.vis;
+    let ffi_name = format_ident!("{}Ffi", name);
+    let ffi_name_str = ffi_name.to_string();
+    let payload_name = format_ident!("{}FfiPayload", name);
+    let payload_name_str = payload_name.to_string();
+    let tag_name_str = format!("{}FfiTag", name);
+    let view_name = format_ident!("{}View", name);
+    let Nesting {
+        ref guard,
+        ref marker,
+        ref marker_init,
+    } = *nesting;
+
+    let c_repr = quote!(::ffi_trait_poc::CRepr);
+    let from_c = quote!(::ffi_trait_poc::FromReprC);
+    let into_c = quote!(::ffi_trait_poc::IntoReprC);
+    let from_borrowed = quote!(::ffi_trait_poc::FromReprCBorrowed);
+    let c_header = quote!(::ffi_trait_poc::CHeader);
+
+    let mut variant_structs = Vec::new();
+    let mut union_fields = Vec::new();
+    let mut owned_arms = Vec::new();
+    let mut cloned_arms = Vec::new();
+    let mut convert_arms = Vec::new();
+    let mut release_arms = Vec::new();
+    let mut view_variants = Vec::new();
+    let mut borrowed_arms = Vec::new();
+    let mut c_variants = Vec::new();
+    let mut c_union_fields = Vec::new();
+    let mut c_tags = Vec::new();
+
+    for (index, variant) in data.variants.iter().enumerate() {
+        if variant.discriminant.is_some() {
+            return Err(Error::new_spanned(
+                variant,
+                "#[derive(ReprC)] does not support explicit discriminants on enums with data",
+            ));
+        }
+
+        let v_ident = &variant.ident;
+        let v_str = v_ident.to_string();
+        let tag = Literal::u32_unsuffixed(index as u32);
+        c_tags.push(quote!((#v_str.to_owned(), #tag)));
+
+        if variant.fields.is_empty() {
+            owned_arms.push(quote!(#tag => Ok(#name::#v_ident {}),));
+            cloned_arms.push(quote!(#tag => Ok(#name::#v_ident {}),));
+            view_variants.push(quote!(#v_ident));
+            borrowed_arms.push(quote!(#tag => Ok(#view_name::#v_ident),));
+            convert_arms.push(quote! {
+                #name::#v_ident { .. } => Ok(#ffi_name {
+                    tag: #tag,
+                    // Nothing is read from the payload of a variant without data.
+                    payload: unsafe { ::std::mem::zeroed() },
+                }),
+            });
+            continue;
+        }
+
+        // Variant fields have no visibility of their own, so take the enum's.
+        let fields = fields(&variant.fields)
+            .into_iter()
+            .map(|f| FieldInfo {
+                vis: vis.clone(),
+                ..f
+            })
+            .collect::<Vec<_>>();
+        let v_struct = format_ident!("{}Ffi{}", name, v_ident);
+        let v_struct_str = v_struct.to_string();
+        let v_field = format_ident!("{}", snake_case(&v_str));
+
+        let members = fields.iter().map(|f| &f.member).collect::<Vec<_>>();
+        let idents = fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+        let bindings = fields
+            .iter()
+            .map(|f| format_ident!("__{}", f.ident))
+            .collect::<Vec<_>>();
+        let tys = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+        let in_fields = fields
+            .iter()
+            .map(|f| in_field(error_ty, Some(&v_field), &f.member))
+            .collect::<Vec<_>>();
+
+        variant_structs.push(quote! {
+            #[repr(C)]
+            #[derive(Debug)]
+            #vis struct #v_struct {
+                #(#vis #idents: <#tys as #c_repr>::C,)*
+                #marker
+            }
+        });
+        union_fields.push(quote!(#vis #v_field: ::std::mem::ManuallyDrop<#v_struct>));
+
+        owned_arms.push(quote! {
+            #tag => {
+                let payload = unsafe { &mut *ffi.payload.#v_field };
+                #(
+                    let #bindings =
+                        <#tys as #from_c>::from_repr_c_owned(&mut payload.#idents)#in_fields;
+                )*
+                Ok(#name::#v_ident {
+                    #(#members: #bindings?,)*
+                })
+            }
+        });
+        cloned_arms.push(quote! {
+            #tag => {
+                let payload = unsafe { &*ffi.payload.#v_field };
+                Ok(#name::#v_ident {
+                    #(#members: <#tys as #from_c>::from_repr_c_cloned(&payload.#idents)#in_fields?,)*
+                })
+            }
+        });
+        release_arms.push(quote! {
+            #tag => {
+                let payload = unsafe { &mut *ffi.payload.#v_field };
+                #(<#tys as #into_c>::release_c(&mut payload.#idents);)*
+            }
+        });
+        convert_arms.push(quote! {
+            #name::#v_ident { #(#members: #bindings,)* } => {
+                #(
+                    let #bindings =
+                        ::ffi_trait_poc::OwnedFfi::<#tys>::new(#bindings)#in_fields?;
+                )*
+                Ok(#ffi_name {
+                    tag: #tag,
+                    payload: #payload_name {
+                        #v_field: ::std::mem::ManuallyDrop::new(#v_struct {
+                            #(#idents: #bindings.into_c(),)*
+                            #marker_init
+                        }),
+                    },
+                })
+            }
+        });
+
+        let view_tys = tys
+            .iter()
+            .map(|ty| quote!(<#ty as #from_borrowed>::View<'a>))
+            .collect::<Vec<_>>();
+        view_variants.push(match variant.fields {
+            Fields::Named(_) => quote!(#v_ident { #(#idents: #view_tys,)* }),
+            _ => quote!(#v_ident(#(#view_tys,)*)),
+        });
+        borrowed_arms.push(quote! {
+            #tag => {
+                let payload = unsafe { &*c.payload.#v_field };
+                Ok(#view_name::#v_ident {
+                    #(#members: <#tys as #from_borrowed>::from_repr_c_borrowed(&payload.#idents)#in_fields?,)*
+                })
+            }
+        });
+
+        c_variants.push(quote! {
+            #(<#tys as #c_header>::declare(header);)*
+            if header.begin_struct(#v_struct_str) {
+                header.add_struct(
+                    #v_struct_str,
+                    vec![#(<#tys as #c_header>::c_decl(stringify!(#idents))),*],
+                );
+            }
+        });
+        c_union_fields.push(quote!(
+            format!("{} {}", #v_struct_str, stringify!(#v_field))
+        ));
+    }
+
+    Ok(quote! {
+        #(#variant_structs)*
+
+        #[repr(C)]
+        #vis union #payload_name {
+            #(#union_fields,)*
+        }
+
+        #[repr(C)]
+        #vis struct #ffi_name {
+            #vis tag: u32,
+            #vis payload: #payload_name,
+        }
+
+        impl ::std::fmt::Debug for #ffi_name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(#ffi_name_str).field("tag", &self.tag).finish()
+            }
+        }
+
+        impl #c_repr for #name {
+            type C = #ffi_name;
+            type Error = #error_ty;
+        }
+
+        impl #from_c for #name {
+            unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                let ffi = unsafe { &mut *c };
+                match ffi.tag {
+                    #(#owned_arms)*
+                    tag => Err(From::from(::ffi_trait_poc::InvalidTag {
+                        type_name: #name_str,
+                        tag: tag,
+                        path: ::ffi_trait_poc::FieldPath::default(),
+                    })),
+                }
+            }
+            unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                #guard
+                let ffi = unsafe { &*c };
+                match ffi.tag {
+                    #(#cloned_arms)*
+                    tag => Err(From::from(::ffi_trait_poc::InvalidTag {
+                        type_name: #name_str,
+                        tag: tag,
+                        path: ::ffi_trait_poc::FieldPath::default(),
+                    })),
+                }
+            }
+        }
+
+        impl #into_c for #name {
+            fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                match self {
+                    #(#convert_arms)*
+                }
+            }
+            unsafe fn release_c(c: *mut Self::C) {
+                let ffi = unsafe { &mut *c };
+                // Variants without data and unknown tags hold nothing to release.
+                match ffi.tag {
+                    #(#release_arms)*
+                    _ => {}
+                }
+            }
+        }
+
+        #[derive(Debug)]
+        #vis enum #view_name<'a> {
+            #(#view_variants,)*
+        }
+
+        impl #from_borrowed for #name {
+            type View<'a> = #view_name<'a>;
+            type SliceView<'a> = Vec<#view_name<'a>>;
+
+            unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+                #guard
+                match c.tag {
+                    #(#borrowed_arms)*
+                    tag => Err(From::from(::ffi_trait_poc::InvalidTag {
+                        type_name: #name_str,
+                        tag: tag,
+                        path: ::ffi_trait_poc::FieldPath::default(),
+                    })),
+                }
+            }
+            unsafe fn slice_from_repr_c_borrowed<'a>(
+                c: &'a ::ffi_trait_poc::FfiVec<Self::C>,
+            ) -> Result<Self::SliceView<'a>, Self::Error> {
+                ::ffi_trait_poc::vec::borrowed_each::<Self>(c)
+            }
+        }
+
+        impl ::ffi_trait_poc::TaggedOption for #ffi_name {}
+
+        impl #c_header for #name {
+            fn c_decl(name: &str) -> String {
+                format!("{} {}", #ffi_name_str, name)
+            }
+            fn c_type_name() -> String {
+                #ffi_name_str.to_owned()
+            }
+            fn declare(header: &mut ::ffi_trait_poc::Header) {
+                if header.begin_struct(#ffi_name_str) {
+                    #(#c_variants)*
+                    if header.begin_struct(#tag_name_str) {
+                        header.add_enum(#tag_name_str, vec![#(#c_tags),*]);
+                    }
+                    if header.begin_struct(#payload_name_str) {
+                        header.add_union(#payload_name_str, vec![#(#c_union_fields),*]);
+                    }
+                    header.add_struct(
+                        #ffi_name_str,
+                        vec![
+                            format!("{} tag", #tag_name_str),
+                            format!("{} payload", #payload_name_str),
+                        ],
+                    );
+                }
+            }
+        }
+    })
+}
+
+fn fields(fields: &Fields) -> Vec<FieldInfo> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match f.ident {
+            Some(ref ident) => FieldInfo {
+                ident: ident.clone(),
+                member: Member::Named(ident.clone()),
+                vis: f.vis.clone(),
+                ty: f.ty.clone(),
+            },
+            None => FieldInfo {
+                ident: format_ident!("_{}", i),
+                member: Member::Unnamed(i.into()),
+                vis: f.vis.clone(),
+                ty: f.ty.clone(),
+            },
+        })
+        .collect()
+}
+
+/// `.map_err(..)` converting a field's conversion error into `error_ty` and marking it as having
+/// happened in the field `member`, of the variant `variant` for enums.
+fn in_field(error_ty: &Type, variant: Option<&Ident>, member: &Member) -> TokenStream2 {
+    let error_path = quote!(<#error_ty as ::ffi_trait_poc::ErrorPath>);
+    let name = match *member {
+        Member::Named(ref ident) => ident.to_string(),
+        Member::Unnamed(ref index) => index.index.to_string(),
+    };
+    let mut e = quote!(#error_path::in_field(From::from(e), #name));
+    if let Some(variant) = variant {
+        let variant = variant.to_string();
+        e = quote!(#error_path::in_field(#e, #variant));
+    }
+    quote!(.map_err(|e| #e))
+}
+
+/// Options given in `#[repr_c(..)]`.
+#[derive(Default)]
+struct ReprCAttrs {
+    /// `error = SomeError`.
+    error: Option<Type>,
+    /// `recursive`, for types recursing only through other types.
+    recursive: bool,
+}
+
+fn repr_c_attrs(input: &DeriveInput) -> Result<ReprCAttrs, Error> {
+    let mut attrs = ReprCAttrs::default();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr_c")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("error") {
+                attrs.error = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else if meta.path.is_ident("recursive") {
+                attrs.recursive = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported repr_c attribute"))
+            }
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// What recursive types need on top of the others.
+struct Nesting {
+    /// Statement entering one level of nesting, at the start of each copy or view from C.
+    guard: TokenStream2,
+    /// Last field of the Ffi structs, and how to initialise it.
+    ///
+    /// The compiler decides whether a struct is `Sized` from its last field alone. Were that a
+    /// field like `Option<Box<Self>>`, it would need the `Sized` of the struct itself to find the
+    /// field's C representation, and give up.
+    marker: TokenStream2,
+    marker_init: TokenStream2,
+}
+
+/// `Nesting` for types that may be recursive, either marked so or with a field that could close a
+/// cycle, and nothing for the others.
+fn nesting(input: &DeriveInput, recursive: bool) -> Nesting {
+    let field_types: Vec<&Type> = match input.data {
+        Data::Struct(ref data) => data.fields.iter().map(|f| &f.ty).collect(),
+        Data::Enum(ref data) => data
+            .variants
+            .iter()
+            .flat_map(|v| v.fields.iter().map(|f| &f.ty))
+            .collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    let may_recurse = field_types.iter().any(|ty| {
+        let tokens = quote!(#ty);
+        mentions(tokens.clone(), &input.ident) || holds_on_heap(tokens)
+    });
+    if recursive || may_recurse {
+        Nesting {
+            guard: quote!(let _nesting = ::ffi_trait_poc::boxed::enter_nested()?;),
+            marker: quote!(_recursive: ::std::marker::PhantomData<()>,),
+            marker_init: quote!(_recursive: ::std::marker::PhantomData,),
+        }
+    } else {
+        Nesting {
+            guard: quote!(),
+            marker: quote!(),
+            marker_init: quote!(),
+        }
+    }
+}
+
+/// Whether `tokens` name the type `ident`, directly or as `Self`.
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ref i) => i == ident || i == "Self",
+        TokenTree::Group(ref group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Containers through which a type can hold a value of its own type, if only via other types.
+const HEAP_CONTAINERS: &[&str] = &[
+    "Box", "Vec", "VecDeque", "HashMap", "BTreeMap", "HashSet", "BTreeSet",
+];
+
+/// Names that cannot close a cycle: leaves, `Option` and the paths to the containers.
+const NOT_RECURSIVE: &[&str] = &[
+    "bool",
+    "char",
+    "f32",
+    "f64",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "String",
+    "Option",
+    "std",
+    "alloc",
+    "boxed",
+    "vec",
+    "collections",
+];
+
+/// Whether `tokens` put a value on the heap whose type might, through other types, hold the one
+/// being derived.
+fn holds_on_heap(tokens: TokenStream2) -> bool {
+    fn idents(tokens: TokenStream2, out: &mut Vec<Ident>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(i) => out.push(i),
+                TokenTree::Group(group) => idents(group.stream(), out),
+                _ => (),
+            }
+        }
+    }
+    let mut names = Vec::new();
+    idents(tokens, &mut names);
+    let is = |list: &[&str], i: &Ident| list.iter().any(|name| i == name);
+    names.iter().any(|i| is(HEAP_CONTAINERS, i))
+        && names
+            .iter()
+            .any(|i| !is(HEAP_CONTAINERS, i) && !is(NOT_RECURSIVE, i))
+}
+
+fn missing_error_type(ident: &Ident) -> Error {
+    Error::new_spanned(
+        ident,
+        "#[derive(ReprC)] requires #[repr_c(error = ErrorType)]",
+    )
+}
+
+fn discriminant(expr: &Expr) -> Result<u32, Error> {
+    match *expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(ref lit),
+            ..
+        }) => lit.base10_parse::<u32>(),
+        _ => Err(Error::new_spanned(
+            expr,
+            "#[derive(ReprC)] only supports integer literal discriminants",
+        )),
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, ch) in name.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i != 0 {
+                out.push('_');
+            }
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
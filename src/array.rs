@@ -0,0 +1,121 @@
+use std::array;
+
+use error::ErrorPath;
+use header::{CHeader, Header};
+use option::TaggedOption;
+use owned::OwnedFfi;
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+// Arrays are represented inline as arrays of their elements' representation, so `[u8; 32]` is
+// `uint8_t key[32]` inside the struct holding it. This is a plain copy for `FfiPod` elements, and
+// `Vec<[T; N]>` of those hands its buffer over as is, like `Vec<T>` does.
+
+impl<T: CRepr, const N: usize> CRepr for [T; N] {
+    type C = [T::C; N];
+    type Error = T::Error;
+}
+
+/// Unwraps `elts` if every element converted, otherwise returns the first error.
+fn all_or_first_error<T, E, const N: usize>(elts: [Option<T>; N], error: Option<E>)
+                                            -> Result<[T; N], E> {
+    match error {
+        Some(e) => Err(e),
+        None => Ok(elts.map(|elt| elt.expect("converted element"))),
+    }
+}
+
+impl<T: FromReprC, const N: usize> FromReprC for [T; N] {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        let c = unsafe { &mut *c };
+        let mut error = None;
+        // Every element is released even if an earlier one fails to convert.
+        let elts = array::from_fn(|i| match unsafe { T::from_repr_c_owned(&mut c[i]) } {
+            Ok(elt) => Some(elt),
+            Err(e) => {
+                error.get_or_insert(e.at_index(i));
+                None
+            }
+        });
+        all_or_first_error(elts, error)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        let c = unsafe { &*c };
+        let mut error = None;
+        let elts = array::from_fn(|i| {
+            if error.is_some() {
+                return None;
+            }
+            unsafe { T::from_repr_c_cloned(&c[i]) }.map_err(|e| error = Some(e.at_index(i))).ok()
+        });
+        all_or_first_error(elts, error)
+    }
+    unsafe fn vec_from_repr_c_owned(c: *mut FfiVec<Self::C>) -> Result<Vec<Self>, Self::Error> {
+        T::array_vec_from_repr_c_owned(c)
+    }
+    unsafe fn vec_from_repr_c_cloned(c: *const FfiVec<Self::C>) -> Result<Vec<Self>, Self::Error> {
+        T::array_vec_from_repr_c_cloned(c)
+    }
+}
+
+impl<T: IntoReprC, const N: usize> IntoReprC for [T; N] {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        let mut elts = IntoIterator::into_iter(self);
+        let mut error = None;
+        let owned: [Option<OwnedFfi<T>>; N] = array::from_fn(|i| {
+            let elt = elts.next().expect("N elements");
+            if error.is_some() {
+                return None;
+            }
+            OwnedFfi::new(elt).map_err(|e| error = Some(e.at_index(i))).ok()
+        });
+        Ok(all_or_first_error(owned, error)?.map(OwnedFfi::into_c))
+    }
+    fn vec_into_repr_c(v: Vec<Self>) -> Result<FfiVec<Self::C>, Self::Error> {
+        T::array_vec_into_repr_c(v)
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        for elt in unsafe { &mut *c } {
+            unsafe { T::release_c(elt) };
+        }
+    }
+}
+
+impl<T: FromReprCBorrowed, const N: usize> FromReprCBorrowed for [T; N] {
+    type View<'a> = [T::View<'a>; N] where T: 'a;
+    type SliceView<'a> = Vec<Self::View<'a>> where T: 'a;
+
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+        let mut error = None;
+        let elts = array::from_fn(|i| {
+            if error.is_some() {
+                return None;
+            }
+            unsafe { T::from_repr_c_borrowed(&c[i]) }.map_err(|e| error = Some(e.at_index(i))).ok()
+        });
+        all_or_first_error(elts, error)
+    }
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error> {
+        vec::borrowed_each::<Self>(c)
+    }
+}
+
+impl<T: CHeader, const N: usize> CHeader for [T; N] {
+    fn c_decl(name: &str) -> String {
+        // `*value` is a pointer to the array, not an array of pointers.
+        if name.starts_with('*') {
+            T::c_decl(&format!("({})[{}]", name, N))
+        } else {
+            T::c_decl(&format!("{}[{}]", name, N))
+        }
+    }
+    fn c_type_name() -> String {
+        format!("{}_{}", T::c_type_name(), N)
+    }
+    fn declare(header: &mut Header) {
+        T::declare(header)
+    }
+}
+
+impl<T, const N: usize> TaggedOption for [T; N] {}
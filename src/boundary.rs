@@ -0,0 +1,94 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use result::ErrorCode;
+
+/// What `catch_panic` does with a panic caught at the C boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Return it as a `Panic`, for the exported function to report to the frontend. The default.
+    Report,
+    /// Abort the process.
+    Abort,
+}
+
+static ABORT_ON_PANIC: AtomicBool = AtomicBool::new(false);
+
+/// Sets the policy of every subsequent `catch_panic`, on all threads.
+pub fn set_panic_policy(policy: PanicPolicy) {
+    ABORT_ON_PANIC.store(policy == PanicPolicy::Abort, Ordering::SeqCst);
+}
+
+pub fn panic_policy() -> PanicPolicy {
+    if ABORT_ON_PANIC.load(Ordering::SeqCst) {
+        PanicPolicy::Abort
+    } else {
+        PanicPolicy::Report
+    }
+}
+
+/// A panic caught at the C boundary.
+#[derive(Debug)]
+pub struct Panic {
+    /// The panic message, if it had one.
+    pub message: String,
+}
+
+impl Panic {
+    fn from_payload(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => (*message).to_owned(),
+                Err(payload) => {
+                    // Its `Drop` could panic again, this time outside of `catch_unwind`.
+                    mem::forget(payload);
+                    "panic with a non-string payload".to_owned()
+                }
+            },
+        };
+        Panic { message }
+    }
+}
+
+impl ErrorCode for Panic {
+    fn error_code(&self) -> i32 {
+        -1
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("Panic", -1)]
+    }
+}
+
+impl Display for Panic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "panicked: {}", self.message)
+    }
+}
+
+impl Error for Panic {}
+
+/// Runs `f`, the body of an exported `extern "C"` function, so that a panic in it never unwinds
+/// into C: depending on `panic_policy` it is returned as `Err(Panic)` or aborts the process.
+///
+/// Values `f` was converting when it panicked are released by the unwinding, so `f` is treated as
+/// unwind safe.
+pub fn catch_panic<F, R>(f: F) -> Result<R, Panic>
+    where F: FnOnce() -> R
+{
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let panic = Panic::from_payload(payload);
+        if panic_policy() == PanicPolicy::Abort {
+            // Not `eprintln!`, which would panic again if stderr is gone.
+            let _ = writeln!(io::stderr(), "ffi-trait-poc: aborting at the C boundary: {}", panic);
+            process::abort();
+        }
+        panic
+    })
+}
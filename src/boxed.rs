@@ -0,0 +1,256 @@
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use error::{self, ErrorPath, FieldPath};
+use header::{CHeader, Header};
+use result::ErrorCode;
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// A boxed or recursive value coming from C that could not be followed, and where.
+#[derive(Debug)]
+pub struct NestingError {
+    pub kind: NestingErrorKind,
+    pub path: FieldPath,
+}
+
+#[derive(Debug)]
+pub enum NestingErrorKind {
+    /// A null pointer was given where a `Box` was required. Use `Option<Box<T>>` for values that
+    /// may be absent.
+    NullPointer,
+    /// Recursive types nested deeper than the `max_depth` given.
+    TooDeep(usize),
+}
+
+impl From<NestingErrorKind> for NestingError {
+    fn from(kind: NestingErrorKind) -> Self {
+        NestingError {
+            kind,
+            path: FieldPath::default(),
+        }
+    }
+}
+
+impl ErrorPath for NestingError {
+    fn path(&self) -> &FieldPath {
+        &self.path
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        &mut self.path
+    }
+}
+
+impl ErrorCode for NestingError {
+    fn error_code(&self) -> i32 {
+        match self.kind {
+            NestingErrorKind::NullPointer => 3,
+            NestingErrorKind::TooDeep(_) => 10,
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("NullPointer", 3), ("TooDeep", 10)]
+    }
+}
+
+impl Display for NestingError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        error::write_path(f, &self.path)?;
+        match self.kind {
+            NestingErrorKind::NullPointer => f.write_str("null pointer where a box was required"),
+            NestingErrorKind::TooDeep(max) => write!(f, "nested deeper than {} levels", max),
+        }
+    }
+}
+
+impl Error for NestingError {}
+
+/// Error of a `Box<T>`: the boxed value's own, or a null pointer where the box was.
+#[derive(Debug)]
+pub enum BoxError<E> {
+    Value(E),
+    Nesting(NestingError),
+}
+
+impl<E: From<NestingError>> BoxError<E> {
+    /// The error as the boxed type's own, e.g. for the `From<BoxError<E>>` a recursive type's
+    /// error needs.
+    pub fn into_inner(self) -> E {
+        match self {
+            BoxError::Value(e) => e,
+            BoxError::Nesting(e) => e.into(),
+        }
+    }
+}
+
+impl<E: ErrorPath> ErrorPath for BoxError<E> {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            BoxError::Value(ref e) => e.path(),
+            BoxError::Nesting(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            BoxError::Value(ref mut e) => e.path_mut(),
+            BoxError::Nesting(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+impl<E: ErrorCode> ErrorCode for BoxError<E> {
+    fn error_code(&self) -> i32 {
+        match *self {
+            BoxError::Value(ref e) => e.error_code(),
+            BoxError::Nesting(ref e) => e.error_code(),
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        let mut codes = E::error_codes();
+        codes.extend(NestingError::error_codes());
+        codes
+    }
+}
+
+impl<E: Display> Display for BoxError<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            BoxError::Value(ref e) => e.fmt(f),
+            BoxError::Nesting(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: Error> Error for BoxError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            BoxError::Value(ref e) => e.source(),
+            BoxError::Nesting(ref e) => e.source(),
+        }
+    }
+}
+
+// -----------------
+
+/// Default of `max_depth`.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DEPTH);
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Sets how many levels of recursive types every subsequent copy or view from C may go through, on
+/// all threads, so that untrusted input cannot overflow the stack.
+pub fn set_max_depth(depth: usize) {
+    MAX_DEPTH.store(depth, Ordering::SeqCst);
+}
+
+pub fn max_depth() -> usize {
+    MAX_DEPTH.load(Ordering::SeqCst)
+}
+
+/// One level of a recursive type being copied or viewed from C, left when dropped.
+#[derive(Debug)]
+pub struct NestingGuard {
+    _private: (),
+}
+
+/// Enters one level of a recursive type, failing with `TooDeep` past `max_depth` levels.
+///
+/// `from_repr_c_cloned` and `from_repr_c_borrowed` derived for recursive types call this first.
+/// Taking ownership is not limited, as it has to release the whole value whether it converts or
+/// not.
+pub fn enter_nested() -> Result<NestingGuard, NestingError> {
+    let max = max_depth();
+    DEPTH.with(|depth| {
+        if depth.get() >= max {
+            return Err(NestingErrorKind::TooDeep(max).into());
+        }
+        depth.set(depth.get() + 1);
+        Ok(NestingGuard { _private: () })
+    })
+}
+
+impl Drop for NestingGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+// -----------------
+
+// A `Box<T>` is a pointer to a `T::C` allocated by Rust, so `Option<Box<T>>` is null for `None`.
+
+fn null_box<E>() -> BoxError<E> {
+    BoxError::Nesting(NestingErrorKind::NullPointer.into())
+}
+
+impl<T: CRepr> CRepr for Box<T> {
+    type C = *mut T::C;
+    type Error = BoxError<T::Error>;
+}
+
+impl<T: FromReprC> FromReprC for Box<T> {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        let ptr = unsafe { *c };
+        if ptr.is_null() {
+            return Err(null_box());
+        }
+        let mut boxed = unsafe { Box::from_raw(ptr) };
+        T::from_repr_c_owned(&mut *boxed).map(Box::new).map_err(BoxError::Value)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        let ptr = unsafe { *c };
+        if ptr.is_null() {
+            return Err(null_box());
+        }
+        T::from_repr_c_cloned(ptr).map(Box::new).map_err(BoxError::Value)
+    }
+}
+
+impl<T: IntoReprC> IntoReprC for Box<T> {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        let c = (*self).into_repr_c().map_err(BoxError::Value)?;
+        Ok(Box::into_raw(Box::new(c)))
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        let ptr = unsafe { *c };
+        if !ptr.is_null() {
+            let mut boxed = unsafe { Box::from_raw(ptr) };
+            unsafe { T::release_c(&mut *boxed) };
+        }
+    }
+}
+
+impl<T: FromReprCBorrowed> FromReprCBorrowed for Box<T> {
+    // Boxed itself so that a recursive type's view is not infinitely large.
+    type View<'a> = Box<T::View<'a>> where T: 'a;
+    type SliceView<'a> = Vec<Self::View<'a>> where T: 'a;
+
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+        if c.is_null() {
+            return Err(null_box());
+        }
+        T::from_repr_c_borrowed(unsafe { &**c }).map(Box::new).map_err(BoxError::Value)
+    }
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error> {
+        vec::borrowed_each::<Self>(c)
+    }
+}
+
+impl<T: CHeader> CHeader for Box<T> {
+    fn c_decl(name: &str) -> String {
+        T::c_decl(&format!("*{}", name))
+    }
+    fn c_type_name() -> String {
+        format!("FfiBox_{}", T::c_type_name())
+    }
+    fn declare(header: &mut Header) {
+        T::declare(header)
+    }
+}
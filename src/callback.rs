@@ -0,0 +1,55 @@
+use std::fmt::Display;
+use std::os::raw::c_void;
+use std::ptr;
+
+use owned::OwnedFfi;
+use result::{ErrorCode, FfiResult};
+use IntoReprC;
+
+/// Signature of a C callback receiving a `T` by its C representation `C`.
+///
+/// `result` and `value` are only valid for the duration of the call; `value` is null unless
+/// `result` is a success.
+pub type CallbackFn<C> = extern "C" fn(user_data: *mut c_void,
+                                       result: *const FfiResult,
+                                       value: *const C);
+
+/// A C callback together with the `user_data` it is given back, receiving a `T`.
+pub struct Callback<T: IntoReprC> {
+    user_data: *mut c_void,
+    f: CallbackFn<T::C>,
+}
+
+// `new` makes its caller promise calls from any thread are fine, as `executor::spawn` calls back
+// from its own.
+unsafe impl<T: IntoReprC> Send for Callback<T> {}
+
+impl<T: IntoReprC> Callback<T> {
+    /// # Safety
+    ///
+    /// `f` may be called with `user_data` from any thread, including one other than the caller's,
+    /// for as long as the `Callback` lives; both must be fit for that.
+    pub unsafe fn new(user_data: *mut c_void, f: CallbackFn<T::C>) -> Self {
+        Callback { user_data, f }
+    }
+
+    /// Converts the value of `res` and calls back with it, releasing it again once the callback
+    /// returns. An error, either `res`'s own or the conversion's, is passed as the `result` with a
+    /// null `value`.
+    pub fn call<E>(&self, res: Result<T, E>)
+        where E: ErrorCode + Display,
+              T::Error: ErrorCode + Display
+    {
+        match res {
+            Ok(value) => match OwnedFfi::new(value) {
+                Ok(c) => (self.f)(self.user_data, &FfiResult::ok(), &*c),
+                Err(e) => self.fail(&e),
+            },
+            Err(e) => self.fail(&e),
+        }
+    }
+
+    fn fail<E: ErrorCode + Display>(&self, e: &E) {
+        (self.f)(self.user_data, &FfiResult::from_error(e), ptr::null());
+    }
+}
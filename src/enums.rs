@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use error::{self, ErrorPath, FieldPath};
+use result::ErrorCode;
+
+/// A tag coming from C that names no variant of the enum it converts to.
+///
+/// Enums with `#[derive(ReprC)]` report this for an unknown tag, so their error type must
+/// implement `From<InvalidTag>`.
+#[derive(Debug)]
+pub struct InvalidTag {
+    /// Name of the Rust enum.
+    pub type_name: &'static str,
+    pub tag: u32,
+    pub path: FieldPath,
+}
+
+impl ErrorPath for InvalidTag {
+    fn path(&self) -> &FieldPath {
+        &self.path
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        &mut self.path
+    }
+}
+
+impl ErrorCode for InvalidTag {
+    fn error_code(&self) -> i32 {
+        6
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("InvalidTag", 6)]
+    }
+}
+
+impl Display for InvalidTag {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        error::write_path(f, &self.path)?;
+        write!(f, "invalid tag {} for {}", self.tag, self.type_name)
+    }
+}
+
+impl Error for InvalidTag {}
@@ -0,0 +1,87 @@
+use std::convert::Infallible;
+use std::fmt::{self, Display, Formatter};
+
+/// Where in a nested value a conversion failed, relative to the value being converted, such as
+/// `c[2].a`. Empty if the value itself failed to convert.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldPath {
+    segments: Vec<Segment>,
+}
+
+/// One step of a `FieldPath`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// A struct or variant field.
+    Field(&'static str),
+    /// An element of a `Vec`.
+    Index(usize),
+}
+
+impl FieldPath {
+    /// The steps from the outermost value inwards.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Adds `segment` in front, as the value containing the failed one does.
+    pub fn prepend(&mut self, segment: Segment) {
+        self.segments.insert(0, segment);
+    }
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match *segment {
+                Segment::Field(name) if i == 0 => f.write_str(name)?,
+                Segment::Field(name) => write!(f, ".{}", name)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Conversion errors, which record the `FieldPath` of the failure as they bubble up through
+/// `Vec`s and derived impls.
+///
+/// Error types wrapping other conversion errors can hand out the path of the wrapped error rather
+/// than keep one of their own.
+pub trait ErrorPath: Sized {
+    fn path(&self) -> &FieldPath;
+    fn path_mut(&mut self) -> &mut FieldPath;
+
+    /// Marks the error as having happened in the field `name`.
+    fn in_field(mut self, name: &'static str) -> Self {
+        self.path_mut().prepend(Segment::Field(name));
+        self
+    }
+
+    /// Marks the error as having happened in the element at `index`.
+    fn at_index(mut self, index: usize) -> Self {
+        self.path_mut().prepend(Segment::Index(index));
+        self
+    }
+}
+
+impl ErrorPath for Infallible {
+    fn path(&self) -> &FieldPath {
+        match *self {}
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {}
+    }
+}
+
+/// Writes `path: ` ahead of an error message, or nothing if `path` is empty.
+pub(crate) fn write_path(f: &mut Formatter, path: &FieldPath) -> fmt::Result {
+    if path.is_empty() {
+        Ok(())
+    } else {
+        write!(f, "{}: ", path)
+    }
+}
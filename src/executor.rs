@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+use boundary;
+use callback::Callback;
+use result::ErrorCode;
+use IntoReprC;
+
+/// Most threads the built-in executor runs futures on.
+const MAX_WORKERS: usize = 4;
+
+/// The future of a `spawn` was cancelled before it completed.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl ErrorCode for Cancelled {
+    fn error_code(&self) -> i32 {
+        7
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("Cancelled", 7)]
+    }
+}
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Runs `future` on the built-in executor and calls back with its converted output.
+///
+/// `callback` is called exactly once, from one of the executor's threads: with the output, with
+/// `Cancelled` if the returned handle cancels the future first, or with the `Panic` of a
+/// panicking future.
+pub fn spawn<F, T, E>(future: F, callback: Callback<T>) -> CancelHandle
+    where F: Future<Output = Result<T, E>> + Send + 'static,
+          T: IntoReprC + 'static,
+          T::Error: ErrorCode + Display,
+          E: ErrorCode + Display + 'static
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let completion = Completion {
+        future: Box::pin(future),
+        callback,
+        cancelled: cancelled.clone(),
+    };
+    let task = Arc::new(Task { future: Mutex::new(Some(Box::pin(completion))) });
+    executor().schedule(task.clone());
+    CancelHandle { cancelled, task }
+}
+
+/// Cancels the future of a `spawn`.
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+    task: Arc<Task>,
+}
+
+impl CancelHandle {
+    /// Drops the future at its next suspension point and calls back with `Cancelled`, unless it
+    /// has completed already.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.task.clone().wake();
+    }
+
+    /// Hands the handle over to C, to be used with `ffi_cancel` and `ffi_cancel_handle_free`.
+    pub fn into_raw(self) -> *mut CancelHandle {
+        Box::into_raw(Box::new(self))
+    }
+}
+
+/// Cancels the future `handle` was returned for, see `CancelHandle::cancel`.
+///
+/// # Safety
+///
+/// `handle` must be null or come from `CancelHandle::into_raw` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ffi_cancel(handle: *const CancelHandle) {
+    if !handle.is_null() {
+        unsafe { (*handle).cancel() };
+    }
+}
+
+/// Releases `handle` without cancelling its future.
+///
+/// # Safety
+///
+/// As for `ffi_cancel`; `handle` must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ffi_cancel_handle_free(handle: *mut CancelHandle) {
+    if !handle.is_null() {
+        let _ = unsafe { Box::from_raw(handle) };
+    }
+}
+
+// -----------------
+
+/// The future of a `spawn` together with what becomes of its output.
+struct Completion<F, T: IntoReprC> {
+    future: Pin<Box<F>>,
+    callback: Callback<T>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<F, T, E> Future for Completion<F, T>
+    where F: Future<Output = Result<T, E>>,
+          T: IntoReprC,
+          T::Error: ErrorCode + Display,
+          E: ErrorCode + Display
+{
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = &mut *self;
+        if this.cancelled.load(Ordering::SeqCst) {
+            this.callback.call(Err::<T, _>(Cancelled));
+            return Poll::Ready(());
+        }
+        match boundary::catch_panic(|| this.future.as_mut().poll(cx)) {
+            Ok(Poll::Ready(res)) => this.callback.call(res),
+            Ok(Poll::Pending) => return Poll::Pending,
+            Err(panic) => this.callback.call(Err::<T, _>(panic)),
+        }
+        Poll::Ready(())
+    }
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A spawned future, `None` once it has completed.
+struct Task {
+    future: Mutex<Option<BoxFuture>>,
+}
+
+impl Task {
+    fn run(self: Arc<Self>) {
+        // Another worker may hold it if the task was woken while being polled; it is polled once
+        // more after that, which futures must cope with.
+        let mut slot = match self.future.lock() {
+            Ok(slot) => slot,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(mut future) = slot.take() {
+            let waker = Waker::from(self.clone());
+            let mut cx = Context::from_waker(&waker);
+            // `Completion` reports panics of the future itself; this keeps the worker alive if
+            // the callback panics as well.
+            if let Ok(Poll::Pending) = boundary::catch_panic(|| future.as_mut().poll(&mut cx)) {
+                *slot = Some(future);
+            }
+        }
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        executor().schedule(self);
+    }
+}
+
+struct Executor {
+    sender: Mutex<Sender<Arc<Task>>>,
+}
+
+impl Executor {
+    fn start() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_WORKERS);
+        for i in 0..workers {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("ffi-trait-poc-worker-{}", i))
+                .spawn(move || work(&receiver))
+                .expect("failed to start an executor thread");
+        }
+        Executor { sender: Mutex::new(sender) }
+    }
+
+    fn schedule(&self, task: Arc<Task>) {
+        // The workers live as long as the process, so the channel stays open.
+        let _ = self.sender.lock().unwrap().send(task);
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Arc<Task>>>) {
+    loop {
+        let task = match receiver.lock().unwrap().recv() {
+            Ok(task) => task,
+            Err(_) => return,
+        };
+        task.run();
+    }
+}
+
+fn executor() -> &'static Executor {
+    static EXECUTOR: OnceLock<Executor> = OnceLock::new();
+    EXECUTOR.get_or_init(Executor::start)
+}
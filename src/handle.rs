@@ -0,0 +1,203 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use header::{CHeader, Header};
+use impl_pod_repr_c;
+use pod::FfiPod;
+use result::ErrorCode;
+
+/// Opaque reference to a value kept in a `Registry`, handed to C in place of the value itself.
+///
+/// Seen from C this is a `uint64_t`; 0 is never a valid handle. Handles are `FfiPod`, so they can
+/// be fields of `ReprC` types.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FfiHandle(pub u64);
+
+unsafe impl FfiPod for FfiHandle {}
+impl_pod_repr_c!(FfiHandle);
+
+impl FfiHandle {
+    fn new(index: u32, generation: u32) -> Self {
+        FfiHandle((u64::from(generation) << 32) | u64::from(index))
+    }
+
+    fn index(self) -> usize {
+        (self.0 & 0xffff_ffff) as usize
+    }
+
+    fn generation(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
+impl CHeader for FfiHandle {
+    fn c_decl(name: &str) -> String {
+        format!("FfiHandle {}", name)
+    }
+    fn c_type_name() -> String {
+        "FfiHandle".to_owned()
+    }
+    fn declare(header: &mut Header) {
+        if header.begin_struct("FfiHandle") {
+            header.add_typedef("uint64_t FfiHandle");
+        }
+    }
+}
+
+/// A handle that does not refer to a value of the registry it was used with, because it was
+/// removed already or never came from there.
+#[derive(Debug)]
+pub struct InvalidHandle {
+    pub handle: FfiHandle,
+}
+
+impl ErrorCode for InvalidHandle {
+    fn error_code(&self) -> i32 {
+        8
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("InvalidHandle", 8)]
+    }
+}
+
+impl Display for InvalidHandle {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid handle {:#x}", self.handle.0)
+    }
+}
+
+impl Error for InvalidHandle {}
+
+/// Thread-safe store of values handed to C as `FfiHandle`s, for types without a `ReprC` layout.
+///
+/// Handles carry the generation of their slot, which changes whenever the slot is reused, so a
+/// stale or already removed handle is reported as `InvalidHandle` instead of reaching another
+/// value. Registries are meant to be `static`s:
+///
+/// ```
+/// # use ffi_trait_poc::handle::Registry;
+/// struct Connection;
+///
+/// static CONNECTIONS: Registry<Connection> = Registry::new();
+///
+/// let handle = CONNECTIONS.insert(Connection);
+/// assert!(CONNECTIONS.get(handle).is_ok());
+/// assert!(CONNECTIONS.remove(handle).is_ok());
+/// assert!(CONNECTIONS.remove(handle).is_err());
+/// ```
+pub struct Registry<T> {
+    slots: RwLock<Slots<T>>,
+}
+
+struct Slots<T> {
+    entries: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<Arc<T>>,
+}
+
+impl<T> Registry<T> {
+    pub const fn new() -> Self {
+        Registry {
+            slots: RwLock::new(Slots {
+                entries: Vec::new(),
+                free: Vec::new(),
+            }),
+        }
+    }
+
+    /// Stores `value`, returning the handle to it.
+    pub fn insert(&self, value: T) -> FfiHandle {
+        let mut slots = self.write();
+        let value = Some(Arc::new(value));
+        match slots.free.pop() {
+            Some(index) => {
+                let slot = &mut slots.entries[index as usize];
+                slot.value = value;
+                FfiHandle::new(index, slot.generation)
+            }
+            None => {
+                let index = slots.entries.len() as u32;
+                slots.entries.push(Slot {
+                    generation: 1,
+                    value,
+                });
+                FfiHandle::new(index, 1)
+            }
+        }
+    }
+
+    /// The value of `handle`, which stays usable even if it is removed meanwhile.
+    pub fn get(&self, handle: FfiHandle) -> Result<Arc<T>, InvalidHandle> {
+        self.read()
+            .entries
+            .get(handle.index())
+            .filter(|slot| slot.generation == handle.generation())
+            .and_then(|slot| slot.value.clone())
+            .ok_or(InvalidHandle { handle })
+    }
+
+    /// Removes the value of `handle`, invalidating the handle.
+    pub fn remove(&self, handle: FfiHandle) -> Result<Arc<T>, InvalidHandle> {
+        let mut slots = self.write();
+        let value = match slots.entries.get_mut(handle.index()) {
+            Some(slot) if slot.generation == handle.generation() && slot.value.is_some() => {
+                // Generation 0 is skipped so that no handle is ever 0.
+                slot.generation = slot.generation.wrapping_add(1).max(1);
+                slot.value.take()
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) => {
+                slots.free.push(handle.index() as u32);
+                Ok(value)
+            }
+            None => Err(InvalidHandle { handle }),
+        }
+    }
+
+    // A panic while holding the lock cannot leave the slots inconsistent, so poisoning is ignored.
+    fn read(&self) -> RwLockReadGuard<'_, Slots<T>> {
+        self.slots.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Slots<T>> {
+        self.slots.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+/// Exports an `extern "C" fn $free(handle: FfiHandle) -> bool` removing `handle` from the
+/// `Registry` `$registry`. It returns `false` and records `InvalidHandle` with `set_last_error`
+/// for a stale, already freed or foreign handle. `Header::register_handle_free` declares it.
+#[macro_export]
+macro_rules! export_handle_free {
+    ($registry:expr, $free:ident) => {
+        #[no_mangle]
+        pub extern "C" fn $free(handle: $crate::handle::FfiHandle) -> bool {
+            let removed = $crate::boundary::catch_panic(|| $registry.remove(handle).map(drop));
+            match removed {
+                Ok(Ok(())) => true,
+                Ok(Err(e)) => {
+                    $crate::last_error::set_last_error(&e);
+                    false
+                }
+                Err(panic) => {
+                    $crate::last_error::set_last_error(&panic);
+                    false
+                }
+            }
+        }
+    }
+}
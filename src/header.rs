@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use boundary::Panic;
+use executor::Cancelled;
+use handle::{FfiHandle, InvalidHandle};
+use result::ErrorCode;
+use CRepr;
+
+/// C declaration of a type's `CRepr::C`, used to generate the header handed to the frontend.
+pub trait CHeader: CRepr {
+    /// Declares `name` with this type's C representation, e.g. `char *name` for a `String`.
+    /// `name` may already carry declarator syntax, such as `*ptr`.
+    fn c_decl(name: &str) -> String;
+    /// Identifier-safe name of the C representation, used to name instantiations like
+    /// `FfiVec_<name>`.
+    fn c_type_name() -> String;
+    /// Name of the C representation of `Vec<Self>`.
+    fn vec_c_type_name() -> String {
+        format!("FfiVec_{}", Self::c_type_name())
+    }
+    /// Adds the struct definitions this representation needs to `header`, dependencies first.
+    fn declare(_header: &mut Header) {}
+}
+
+/// Collects the C declarations of registered `ReprC` types and renders them as a header.
+pub struct Header {
+    guard: String,
+    known: HashSet<String>,
+    items: Vec<Item>,
+    functions: Vec<String>,
+    error_codes: Vec<(String, i32)>,
+}
+
+enum Item {
+    Struct(String, Vec<String>),
+    Union(String, Vec<String>),
+    Enum(String, Vec<(String, u32)>),
+    /// A complete `typedef`, without the trailing semicolon.
+    Typedef(String),
+}
+
+impl Header {
+    /// Starts an empty header protected by the include guard `guard`.
+    pub fn new(guard: &str) -> Self {
+        Header {
+            guard: guard.to_owned(),
+            known: HashSet::new(),
+            items: Vec::new(),
+            functions: Vec::new(),
+            error_codes: Vec::new(),
+        }
+    }
+
+    /// Declares the C representation of `T`.
+    pub fn register<T: CHeader>(&mut self) -> &mut Self {
+        T::declare(self);
+        self
+    }
+
+    /// Declares the C representation of `T` and the prototypes of the `free` and `clone`
+    /// functions `export_ffi_fns!` exported for it.
+    pub fn register_ffi_fns<T: CHeader>(&mut self, free: &str, clone: &str) -> &mut Self {
+        T::declare(self);
+        self.functions.push(format!("void {}({});", free, T::c_decl("*value")));
+        self.functions.push(format!("bool {}(const {}, {});",
+                                    clone,
+                                    T::c_decl("*value"),
+                                    T::c_decl("*out")));
+        self
+    }
+
+    /// Declares `FfiResult` and the last error functions, and adds the codes of `E` to the
+    /// `FfiErrorCode` enum, which always includes `FfiErrorCode_Ok` and `FfiErrorCode_Panic`.
+    pub fn register_error_codes<E: ErrorCode>(&mut self) -> &mut Self {
+        self.declare_result();
+        self.add_error_codes(E::error_codes());
+        self
+    }
+
+    /// Declares `<name>Callback`, the `CallbackFn` receiving a `T`, `<name>` being its
+    /// `c_type_name`.
+    pub fn register_callback<T: CHeader>(&mut self) -> &mut Self {
+        self.declare_result();
+        T::declare(self);
+        let name = format!("{}Callback", T::c_type_name());
+        if self.begin_struct(&name) {
+            let typedef = format!("void (*{})(void *user_data, const FfiResult *result, const {})",
+                                  name,
+                                  T::c_decl("*value"));
+            self.add_typedef(&typedef);
+        }
+        self
+    }
+
+    /// Declares the opaque `FfiCancelHandle` returned to the frontend for futures run by
+    /// `executor::spawn`, and the functions cancelling and releasing it.
+    pub fn register_cancel_handle(&mut self) -> &mut Self {
+        if self.begin_struct("FfiCancelHandle") {
+            self.add_typedef("struct FfiCancelHandle FfiCancelHandle");
+            self.functions.push("void ffi_cancel(const FfiCancelHandle *handle);".to_owned());
+            self.functions.push("void ffi_cancel_handle_free(FfiCancelHandle *handle);".to_owned());
+        }
+        self
+    }
+
+    /// Declares `bool <free>(FfiHandle handle);`, exported by `export_handle_free!`.
+    pub fn register_handle_free(&mut self, free: &str) -> &mut Self {
+        self.declare_result();
+        self.add_error_codes(InvalidHandle::error_codes());
+        FfiHandle::declare(self);
+        self.functions.push(format!("bool {}(FfiHandle handle);", free));
+        self
+    }
+
+    fn declare_result(&mut self) {
+        if self.begin_struct("FfiResult") {
+            self.add_error_codes(vec![("Ok", 0)]);
+            self.add_error_codes(Panic::error_codes());
+            self.add_error_codes(Cancelled::error_codes());
+            self.add_struct("FfiResult",
+                            vec!["FfiErrorCode error_code".to_owned(),
+                                 "const char *description".to_owned()]);
+            self.functions.push("FfiErrorCode ffi_last_error_code(void);".to_owned());
+            self.functions.push("size_t ffi_last_error_message(char *buf, size_t len);".to_owned());
+            self.functions.push("void ffi_clear_last_error(void);".to_owned());
+        }
+    }
+
+    fn add_error_codes(&mut self, codes: Vec<(&str, i32)>) {
+        for (name, code) in codes {
+            if !self.error_codes.iter().any(|(known, _)| known == name) {
+                self.error_codes.push((name.to_owned(), code));
+            }
+        }
+    }
+
+    /// Reserves the struct, union or enum `name`. Returns `false` if it was already reserved, in
+    /// which case the caller must not declare it again. Reserving before declaring the fields' own
+    /// structs lets recursive types terminate.
+    pub fn begin_struct(&mut self, name: &str) -> bool {
+        self.known.insert(name.to_owned())
+    }
+
+    /// Defines the struct `name` previously reserved with `begin_struct`. Each entry of `fields`
+    /// is a complete declaration as produced by `CHeader::c_decl`.
+    pub fn add_struct(&mut self, name: &str, fields: Vec<String>) {
+        self.items.push(Item::Struct(name.to_owned(), fields));
+    }
+
+    /// Defines the union `name`, like `add_struct`.
+    pub fn add_union(&mut self, name: &str, fields: Vec<String>) {
+        self.items.push(Item::Union(name.to_owned(), fields));
+    }
+
+    /// Defines `name` as a `uint32_t` together with a `<name>_<variant>` constant for each of
+    /// `variants`.
+    pub fn add_enum(&mut self, name: &str, variants: Vec<(String, u32)>) {
+        self.items.push(Item::Enum(name.to_owned(), variants));
+    }
+
+    /// Adds `typedef <typedef>;`, e.g. `uint64_t FfiHandle`, previously reserved with
+    /// `begin_struct`.
+    pub fn add_typedef(&mut self, typedef: &str) {
+        self.items.push(Item::Typedef(typedef.to_owned()));
+    }
+
+    /// Writes the rendered header to `path`.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        File::create(path)?.write_all(self.render().as_bytes())
+    }
+
+    /// Renders the header.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("/* Generated by ffi-trait-poc. Do not edit. */\n\n");
+        out.push_str(&format!("#ifndef {0}\n#define {0}\n\n", self.guard));
+        out.push_str("#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n");
+        out.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+
+        // Forward declare everything so structs may point at ones defined later.
+        for item in &self.items {
+            match *item {
+                Item::Struct(ref name, _) => {
+                    out.push_str(&format!("typedef struct {0} {0};\n", name))
+                }
+                Item::Union(ref name, _) => {
+                    out.push_str(&format!("typedef union {0} {0};\n", name))
+                }
+                Item::Enum(..) | Item::Typedef(_) => (),
+            }
+        }
+        if !self.error_codes.is_empty() {
+            out.push_str("\ntypedef int32_t FfiErrorCode;\n");
+            out.push_str("enum {\n");
+            for &(ref name, code) in &self.error_codes {
+                out.push_str(&format!("    FfiErrorCode_{} = {},\n", name, code));
+            }
+            out.push_str("};\n");
+        }
+        for item in &self.items {
+            match *item {
+                Item::Struct(ref name, ref fields) => {
+                    render_fields(&mut out, "struct", name, fields)
+                }
+                Item::Union(ref name, ref fields) => render_fields(&mut out, "union", name, fields),
+                Item::Enum(ref name, ref variants) => {
+                    out.push_str(&format!("\ntypedef uint32_t {};\n", name));
+                    out.push_str("enum {\n");
+                    for &(ref variant, value) in variants {
+                        out.push_str(&format!("    {}_{} = {},\n", name, variant, value));
+                    }
+                    out.push_str("};\n");
+                }
+                Item::Typedef(ref typedef) => out.push_str(&format!("\ntypedef {};\n", typedef)),
+            }
+        }
+        out.push('\n');
+        for function in &self.functions {
+            out.push_str(function);
+            out.push('\n');
+        }
+
+        out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n");
+        out.push_str(&format!("#endif /* {} */\n", self.guard));
+        out
+    }
+}
+
+fn render_fields(out: &mut String, keyword: &str, name: &str, fields: &[String]) {
+    out.push_str(&format!("\n{} {} {{\n", keyword, name));
+    for field in fields {
+        out.push_str(&format!("    {};\n", field));
+    }
+    out.push_str("};\n");
+}
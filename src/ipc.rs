@@ -0,0 +1,164 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use boxed::NestingError;
+use error::{ErrorPath, FieldPath};
+use result::ErrorCode;
+use strings::StringError;
+use ReprC;
+
+#[derive(Debug)]
+pub enum IpcError {
+    StringError(StringError),
+    NestingError(NestingError),
+}
+
+impl From<StringError> for IpcError {
+    fn from(e: StringError) -> Self {
+        IpcError::StringError(e)
+    }
+}
+impl From<NestingError> for IpcError {
+    fn from(e: NestingError) -> Self {
+        IpcError::NestingError(e)
+    }
+}
+impl From<Infallible> for IpcError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl ErrorPath for IpcError {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            IpcError::StringError(ref e) => e.path(),
+            IpcError::NestingError(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            IpcError::StringError(ref mut e) => e.path_mut(),
+            IpcError::NestingError(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+impl ErrorCode for IpcError {
+    fn error_code(&self) -> i32 {
+        match *self {
+            IpcError::StringError(ref e) => e.error_code(),
+            IpcError::NestingError(ref e) => e.error_code(),
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        let mut codes = StringError::error_codes();
+        codes.extend(NestingError::error_codes());
+        codes
+    }
+}
+
+impl Display for IpcError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            IpcError::StringError(ref e) => e.fmt(f),
+            IpcError::NestingError(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for IpcError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            IpcError::StringError(ref e) => e.source(),
+            IpcError::NestingError(ref e) => e.source(),
+        }
+    }
+}
+
+// -----------------
+
+#[derive(Clone, ReprC)]
+#[repr_c(error = IpcError)]
+pub struct One {
+    pub a: String,
+}
+
+// -----------------
+
+#[derive(ReprC)]
+#[repr_c(error = IpcError)]
+pub struct Two {
+    pub a: String,
+    pub b: Vec<u8>,
+    pub c: Vec<One>,
+    pub d: One,
+}
+
+// -----------------
+
+/// Exports `extern "C"` functions releasing and deep-copying the C representation of `$ty`, named
+/// `$free` and `$clone`. `Header::register_ffi_fns` declares them.
+///
+/// `$free(value)` takes back ownership of everything `*value` points to; `*value` itself is not
+/// freed and must not be used again. `$clone(value, out)` writes an independent copy of `*value`
+/// to `*out` and returns `false` without touching `*out` if the copy could not be made.
+///
+/// Both run under `catch_panic`. A failure, panics included, is recorded with `set_last_error`, so
+/// the error type of `$ty` must implement `ErrorCode` and `Display`. Both are `unsafe` to call from
+/// Rust, as they trust `value` to be valid; `OwnedFfi` is the safe way to hold a `$ty` there.
+#[macro_export]
+macro_rules! export_ffi_fns {
+    ($ty:ty, $free:ident, $clone:ident) => {
+        /// # Safety
+        ///
+        /// `value` must be null or satisfy `IntoReprC::release_c`.
+        #[no_mangle]
+        pub unsafe extern "C" fn $free(value: *mut <$ty as $crate::CRepr>::C) {
+            if value.is_null() {
+                return;
+            }
+            let released = $crate::boundary::catch_panic(|| unsafe {
+                <$ty as $crate::IntoReprC>::release_c(value)
+            });
+            if let Err(panic) = released {
+                $crate::last_error::set_last_error(&panic);
+            }
+        }
+
+        /// # Safety
+        ///
+        /// `value` must be null or satisfy `FromReprC::from_repr_c_cloned`, and `out` must be null
+        /// or valid for writes.
+        #[no_mangle]
+        pub unsafe extern "C" fn $clone(value: *const <$ty as $crate::CRepr>::C,
+                                        out: *mut <$ty as $crate::CRepr>::C)
+                                        -> bool {
+            if value.is_null() || out.is_null() {
+                return false;
+            }
+            let copy = $crate::boundary::catch_panic(|| unsafe {
+                <$ty as $crate::FromReprC>::from_repr_c_cloned(value)
+                    .and_then(<$ty as $crate::IntoReprC>::into_repr_c)
+            });
+            match copy {
+                Ok(Ok(copy)) => {
+                    unsafe { ::std::ptr::write(out, copy) };
+                    true
+                }
+                Ok(Err(e)) => {
+                    $crate::last_error::set_last_error(&e);
+                    false
+                }
+                Err(panic) => {
+                    $crate::last_error::set_last_error(&panic);
+                    false
+                }
+            }
+        }
+    }
+}
+
+export_ffi_fns!(One, one_ffi_free, one_ffi_clone);
+export_ffi_fns!(Two, two_ffi_free, two_ffi_clone);
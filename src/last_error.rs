@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::ffi::CString;
+use std::fmt::Display;
+use std::os::raw::c_char;
+use std::ptr;
+
+use result::{self, ErrorCode};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(i32, CString)>> = const { RefCell::new(None) };
+}
+
+/// Records `e` as the last error of the calling thread, for the frontend to query errno style.
+///
+/// The exported functions record their failures here; a later success leaves the record alone.
+pub fn set_last_error<E: ErrorCode + Display>(e: &E) {
+    let record = (e.error_code(), result::describe(e));
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(record));
+}
+
+pub fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// Code of the last error recorded on the calling thread, 0 if there is none.
+pub fn last_error_code() -> i32 {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(0, |&(code, _)| code))
+}
+
+/// Message of the last error recorded on the calling thread.
+pub fn last_error_message() -> Option<String> {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|(_, message)| message.to_string_lossy().into_owned())
+    })
+}
+
+/// Code of the last error recorded on the calling thread, `FfiErrorCode_Ok` if there is none.
+#[no_mangle]
+pub extern "C" fn ffi_last_error_code() -> i32 {
+    last_error_code()
+}
+
+/// Copies the message of the last error recorded on the calling thread to `buf`, truncated to
+/// `len - 1` bytes and nul terminated, like `snprintf`. Returns the length of the whole message
+/// without its terminator, 0 if there is no error.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ffi_last_error_message(buf: *mut c_char, len: usize) -> usize {
+    LAST_ERROR.with(|last| {
+        let last = last.borrow();
+        let message = last.as_ref().map_or(&[][..], |(_, message)| message.as_bytes());
+        if !buf.is_null() && len > 0 {
+            let copied = cmp::min(message.len(), len - 1);
+            unsafe {
+                ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buf, copied);
+                *buf.add(copied) = 0;
+            }
+        }
+        message.len()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ffi_clear_last_error() {
+    clear_last_error()
+}
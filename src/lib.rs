@@ -0,0 +1,184 @@
+//! Conversion of Rust types to and from `#[repr(C)]` representations that can be handed to a C
+//! frontend, built as a `cdylib`/`staticlib` exporting the functions to release them.
+
+extern crate ffi_trait_poc_derive;
+
+// Lets the code generated by `#[derive(ReprC)]` name this crate the same way from inside it as from
+// its dependents.
+extern crate self as ffi_trait_poc;
+
+pub use boundary::{catch_panic, set_panic_policy, Panic, PanicPolicy};
+pub use boxed::{set_max_depth, BoxError, NestingError, NestingErrorKind};
+pub use callback::{Callback, CallbackFn};
+pub use enums::InvalidTag;
+pub use executor::{spawn, CancelHandle, Cancelled};
+pub use error::{ErrorPath, FieldPath, Segment};
+pub use ffi_trait_poc_derive::ReprC;
+pub use handle::{FfiHandle, InvalidHandle, Registry};
+pub use header::{CHeader, Header};
+pub use last_error::{clear_last_error, last_error_code, last_error_message, set_last_error};
+pub use map::{DuplicateKey, FfiEntry, FfiMap, MapError};
+pub use option::{FfiNullable, FfiOption, OptionC, TaggedOption};
+pub use owned::OwnedFfi;
+pub use pod::FfiPod;
+pub use primitives::{PrimitiveError, PrimitiveErrorKind};
+pub use result::{ErrorCode, FfiResult};
+pub use set::SetError;
+pub use strings::{StringError, StringErrorKind};
+pub use vec::{FfiBytes, FfiVec};
+
+pub mod array;
+pub mod boundary;
+pub mod boxed;
+pub mod callback;
+pub mod enums;
+pub mod error;
+pub mod executor;
+pub mod handle;
+pub mod header;
+pub mod ipc;
+pub mod last_error;
+pub mod map;
+pub mod option;
+pub mod owned;
+pub mod pod;
+pub mod primitives;
+pub mod result;
+pub mod set;
+pub mod strings;
+pub mod tuple;
+pub mod vec;
+
+// -------------------- Our Trait ------------------------
+
+/// The C representation of a type, shared by both directions of conversion.
+///
+/// A `C` is *valid* when every pointer in it points to what its C layout describes: a nul
+/// terminated string, a live buffer of `len` initialised elements, the active variant of a tagged
+/// union, and so on down to the leaves. Conversions from C rely on this without being able to check
+/// it, which is why they are `unsafe`; `OwnedFfi` wraps them safely for values that came from Rust.
+///
+/// A `C` owns its allocations but has no `Drop` releasing them, so that dropping a copy cannot
+/// release them twice: `FromReprC::from_repr_c_owned` and `IntoReprC::release_c` do, one of which
+/// `OwnedFfi` calls exactly once.
+pub trait CRepr {
+    type C;
+    type Error: ErrorPath;
+}
+
+/// Conversion from the C representation, for types received from C.
+pub trait FromReprC: CRepr {
+    /// Takes ownership of `*c` whether or not the conversion succeeds: on error everything it
+    /// owned has been released, and `*c` must not be used again.
+    ///
+    /// # Safety
+    ///
+    /// `c` must point to a valid `Self::C` whose allocations were all made by `into_repr_c` and not
+    /// released since. Afterwards `*c` must not be used again.
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> where Self: Sized;
+    /// # Safety
+    ///
+    /// `c` must point to a valid `Self::C` for the duration of the call.
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> where Self: Sized;
+
+    /// Converts a whole `Vec<Self>`, which `Vec<T>` defers to. The default converts element by
+    /// element; `FfiPod` types override these to hand the buffer over as is.
+    ///
+    /// # Safety
+    ///
+    /// As for `from_repr_c_owned`.
+    unsafe fn vec_from_repr_c_owned(c: *mut FfiVec<Self::C>) -> Result<Vec<Self>, Self::Error>
+        where Self: Sized
+    {
+        vec::from_repr_c_owned_each(c)
+    }
+    /// # Safety
+    ///
+    /// As for `from_repr_c_cloned`.
+    unsafe fn vec_from_repr_c_cloned(c: *const FfiVec<Self::C>) -> Result<Vec<Self>, Self::Error>
+        where Self: Sized
+    {
+        vec::from_repr_c_cloned_each(c)
+    }
+
+    /// Converts a whole `Vec<[Self; N]>`, which `Vec<[T; N]>` defers to. The default converts
+    /// array by array; `FfiPod` types override these as well.
+    ///
+    /// # Safety
+    ///
+    /// As for `from_repr_c_owned`.
+    unsafe fn array_vec_from_repr_c_owned<const N: usize>(c: *mut FfiVec<[Self::C; N]>)
+                                                          -> Result<Vec<[Self; N]>, Self::Error>
+        where Self: Sized
+    {
+        vec::from_repr_c_owned_each::<[Self; N]>(c)
+    }
+    /// # Safety
+    ///
+    /// As for `from_repr_c_cloned`.
+    unsafe fn array_vec_from_repr_c_cloned<const N: usize>(c: *const FfiVec<[Self::C; N]>)
+                                                           -> Result<Vec<[Self; N]>, Self::Error>
+        where Self: Sized
+    {
+        vec::from_repr_c_cloned_each::<[Self; N]>(c)
+    }
+}
+
+/// Conversion into the C representation, for types handed to C.
+pub trait IntoReprC: CRepr {
+    /// On error nothing is left allocated on the C side.
+    fn into_repr_c(self) -> Result<Self::C, Self::Error>;
+
+    /// Releases what `into_repr_c` made without converting it back, e.g. the fields converted
+    /// already when a later one fails. Types only ever handed to C need no `FromReprC` for this.
+    ///
+    /// # Safety
+    ///
+    /// `c` must point to a valid `Self::C` whose allocations were all made by `into_repr_c` and not
+    /// released since. Afterwards `*c` must not be used again.
+    unsafe fn release_c(c: *mut Self::C);
+
+    /// Converts a whole `Vec<Self>`, which `Vec<T>` defers to. Should an element fail, the ones
+    /// converted before it are released again.
+    fn vec_into_repr_c(v: Vec<Self>) -> Result<FfiVec<Self::C>, Self::Error>
+        where Self: Sized
+    {
+        vec::into_repr_c_each(v)
+    }
+
+    /// Converts a whole `Vec<[Self; N]>`, which `Vec<[T; N]>` defers to.
+    fn array_vec_into_repr_c<const N: usize>(v: Vec<[Self; N]>)
+                                             -> Result<FfiVec<[Self::C; N]>, Self::Error>
+        where Self: Sized
+    {
+        vec::into_repr_c_each(v)
+    }
+}
+
+/// Both directions of conversion, implemented for every type that is `FromReprC` and `IntoReprC`.
+pub trait ReprC: FromReprC + IntoReprC {}
+
+impl<T: FromReprC + IntoReprC> ReprC for T {}
+
+/// Validating, non-allocating read access to a `CRepr::C` owned by someone else, for data only
+/// needed for the duration of a call.
+///
+/// A view borrows from the C representation, e.g. `&'a str` for a `String` and `&'a [u8]` for a
+/// `Vec<u8>`, and `#[derive(ReprC)]` generates a `XxxView<'a>` mirroring `Xxx`. Only the list of
+/// views of a `Vec` whose elements are not `FfiPod` is allocated.
+pub trait FromReprCBorrowed: FromReprC {
+    type View<'a> where Self: 'a;
+    /// View of a `Vec<Self>`, which `Vec<T>` defers to: `&'a [Self]` for `FfiPod` types, a `Vec`
+    /// of views otherwise.
+    type SliceView<'a> where Self: 'a;
+
+    /// # Safety
+    ///
+    /// `*c` must be valid for as long as the view lives.
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error>;
+    /// # Safety
+    ///
+    /// As for `from_repr_c_borrowed`.
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error>;
+}
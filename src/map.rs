@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hash;
+
+use error::{self, ErrorPath, FieldPath};
+use header::{CHeader, Header};
+use owned::OwnedFfi;
+use result::ErrorCode;
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// `#[repr(C)]` entry of a map, `struct { K key; V value; }` seen from C.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiEntry<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+/// `#[repr(C)]` representation of a `HashMap` or `BTreeMap`: the `FfiVec` of its entries, in the
+/// map's iteration order, so sorted by key for a `BTreeMap`.
+pub type FfiMap<K, V> = FfiVec<FfiEntry<K, V>>;
+
+/// A key coming from C more than once for the same map, or an element for the same set.
+#[derive(Debug)]
+pub struct DuplicateKey {
+    pub path: FieldPath,
+}
+
+impl ErrorPath for DuplicateKey {
+    fn path(&self) -> &FieldPath {
+        &self.path
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        &mut self.path
+    }
+}
+
+impl ErrorCode for DuplicateKey {
+    fn error_code(&self) -> i32 {
+        9
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("DuplicateKey", 9)]
+    }
+}
+
+impl Display for DuplicateKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        error::write_path(f, &self.path)?;
+        f.write_str("duplicate key")
+    }
+}
+
+impl Error for DuplicateKey {}
+
+/// Error converting a map, from one of its keys or values or a duplicate key.
+#[derive(Debug)]
+pub enum MapError<K, V> {
+    Key(K),
+    Value(V),
+    DuplicateKey(DuplicateKey),
+}
+
+impl<K: ErrorPath, V: ErrorPath> ErrorPath for MapError<K, V> {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            MapError::Key(ref e) => e.path(),
+            MapError::Value(ref e) => e.path(),
+            MapError::DuplicateKey(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            MapError::Key(ref mut e) => e.path_mut(),
+            MapError::Value(ref mut e) => e.path_mut(),
+            MapError::DuplicateKey(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+impl<K: ErrorCode, V: ErrorCode> ErrorCode for MapError<K, V> {
+    fn error_code(&self) -> i32 {
+        match *self {
+            MapError::Key(ref e) => e.error_code(),
+            MapError::Value(ref e) => e.error_code(),
+            MapError::DuplicateKey(ref e) => e.error_code(),
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        let mut codes = K::error_codes();
+        codes.extend(V::error_codes());
+        codes.extend(DuplicateKey::error_codes());
+        codes
+    }
+}
+
+impl<K: Display, V: Display> Display for MapError<K, V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            MapError::Key(ref e) => e.fmt(f),
+            MapError::Value(ref e) => e.fmt(f),
+            MapError::DuplicateKey(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<K: Error, V: Error> Error for MapError<K, V> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            MapError::Key(ref e) => e.source(),
+            MapError::Value(ref e) => e.source(),
+            MapError::DuplicateKey(ref e) => e.source(),
+        }
+    }
+}
+
+// -----------------
+
+/// Key and value of one entry; maps are converted as a `Vec` of these.
+struct Entry<K, V> {
+    key: K,
+    value: V,
+}
+
+fn key_error<K: ErrorPath, V>(e: K) -> MapError<K, V> {
+    MapError::Key(e.in_field("key"))
+}
+
+fn value_error<K, V: ErrorPath>(e: V) -> MapError<K, V> {
+    MapError::Value(e.in_field("value"))
+}
+
+impl<K: CRepr, V: CRepr> CRepr for Entry<K, V> {
+    type C = FfiEntry<K::C, V::C>;
+    type Error = MapError<K::Error, V::Error>;
+}
+
+impl<K: FromReprC, V: FromReprC> FromReprC for Entry<K, V> {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        let c = unsafe { &mut *c };
+        // Take both before returning an error so that neither is left unreleased.
+        let key = K::from_repr_c_owned(&mut c.key);
+        let value = V::from_repr_c_owned(&mut c.value);
+        Ok(Entry {
+            key: key.map_err(key_error)?,
+            value: value.map_err(value_error)?,
+        })
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        let c = unsafe { &*c };
+        Ok(Entry {
+            key: K::from_repr_c_cloned(&c.key).map_err(key_error)?,
+            value: V::from_repr_c_cloned(&c.value).map_err(value_error)?,
+        })
+    }
+}
+
+impl<K: IntoReprC, V: IntoReprC> IntoReprC for Entry<K, V> {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        let key = OwnedFfi::new(self.key).map_err(key_error)?;
+        let value = OwnedFfi::new(self.value).map_err(value_error)?;
+        Ok(FfiEntry {
+            key: key.into_c(),
+            value: value.into_c(),
+        })
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        unsafe {
+            K::release_c(&mut (*c).key);
+            V::release_c(&mut (*c).value);
+        }
+    }
+}
+
+impl<K: FromReprCBorrowed, V: FromReprCBorrowed> FromReprCBorrowed for Entry<K, V> {
+    type View<'a> = (K::View<'a>, V::View<'a>) where K: 'a, V: 'a;
+    type SliceView<'a> = Vec<Self::View<'a>> where K: 'a, V: 'a;
+
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+        Ok((K::from_repr_c_borrowed(&c.key).map_err(key_error)?,
+            V::from_repr_c_borrowed(&c.value).map_err(value_error)?))
+    }
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error> {
+        vec::borrowed_each::<Self>(c)
+    }
+}
+
+impl<K: CHeader, V: CHeader> CHeader for Entry<K, V> {
+    fn c_decl(name: &str) -> String {
+        format!("{} {}", Self::c_type_name(), name)
+    }
+    fn c_type_name() -> String {
+        format!("FfiEntry_{}_{}", K::c_type_name(), V::c_type_name())
+    }
+    fn vec_c_type_name() -> String {
+        format!("FfiMap_{}_{}", K::c_type_name(), V::c_type_name())
+    }
+    fn declare(header: &mut Header) {
+        let name = Self::c_type_name();
+        if header.begin_struct(&name) {
+            K::declare(header);
+            V::declare(header);
+            header.add_struct(&name, vec![K::c_decl("key"), V::c_decl("value")]);
+        }
+    }
+}
+
+/// Builds a map from the entries converted from C, failing on the first repeated key.
+fn collect_entries<K, V, M>(entries: Vec<Entry<K, V>>,
+                            mut map: M,
+                            insert: fn(&mut M, K, V) -> Option<V>)
+                            -> Result<M, MapError<K::Error, V::Error>>
+    where K: CRepr,
+          V: CRepr
+{
+    for (i, entry) in entries.into_iter().enumerate() {
+        if insert(&mut map, entry.key, entry.value).is_some() {
+            let e = DuplicateKey { path: FieldPath::default() };
+            return Err(MapError::DuplicateKey(e).in_field("key").at_index(i));
+        }
+    }
+    Ok(map)
+}
+
+macro_rules! impl_map {
+    ($map:ident, $new:expr, $($key_bound:tt)+) => {
+        impl<K: CRepr, V: CRepr> CRepr for $map<K, V> {
+            type C = FfiMap<K::C, V::C>;
+            type Error = MapError<K::Error, V::Error>;
+        }
+
+        impl<K: FromReprC + $($key_bound)+, V: FromReprC> FromReprC for $map<K, V> {
+            unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                let entries = Vec::<Entry<K, V>>::from_repr_c_owned(c)?;
+                let map = $new(entries.len());
+                collect_entries(entries, map, $map::insert)
+            }
+            unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                let entries = Vec::<Entry<K, V>>::from_repr_c_cloned(c)?;
+                let map = $new(entries.len());
+                collect_entries(entries, map, $map::insert)
+            }
+        }
+
+        impl<K: IntoReprC + $($key_bound)+, V: IntoReprC> IntoReprC for $map<K, V> {
+            fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                vec::into_repr_c_each(self.into_iter().map(|(key, value)| Entry { key, value }))
+            }
+            unsafe fn release_c(c: *mut Self::C) {
+                Vec::<Entry<K, V>>::release_c(c)
+            }
+        }
+
+        /// The view lists the entries as they are, without checking the keys for duplicates.
+        impl<K, V> FromReprCBorrowed for $map<K, V>
+            where K: FromReprCBorrowed + $($key_bound)+,
+                  V: FromReprCBorrowed
+        {
+            type View<'a> = Vec<(K::View<'a>, V::View<'a>)> where K: 'a, V: 'a;
+            type SliceView<'a> = Vec<Self::View<'a>> where K: 'a, V: 'a;
+
+            unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C)
+                                               -> Result<Self::View<'a>, Self::Error> {
+                vec::borrowed_each::<Entry<K, V>>(c)
+            }
+            unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                                     -> Result<Self::SliceView<'a>, Self::Error> {
+                vec::borrowed_each::<Self>(c)
+            }
+        }
+
+        impl<K: CHeader, V: CHeader> CHeader for $map<K, V> {
+            fn c_decl(name: &str) -> String {
+                Vec::<Entry<K, V>>::c_decl(name)
+            }
+            fn c_type_name() -> String {
+                Vec::<Entry<K, V>>::c_type_name()
+            }
+            fn declare(header: &mut Header) {
+                Vec::<Entry<K, V>>::declare(header)
+            }
+        }
+    }
+}
+
+impl_map!(HashMap, HashMap::with_capacity, Eq + Hash);
+impl_map!(BTreeMap, |_| BTreeMap::new(), Ord);
@@ -0,0 +1,218 @@
+use std::fmt::{self, Debug, Formatter};
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::ptr;
+
+use header::{CHeader, Header};
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// `#[repr(C)]` representation of an `Option<T>` whose C representation has no null value.
+///
+/// Seen from C this is `struct { bool is_some; T value; }`. `value` is only initialised when
+/// `is_some` is true.
+#[repr(C)]
+pub struct FfiOption<T> {
+    pub is_some: bool,
+    pub value: MaybeUninit<T>,
+}
+
+// The fields are public, so `is_some` cannot be trusted to tell whether `value` is initialised.
+impl<T> Debug for FfiOption<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("FfiOption").field("is_some", &self.is_some).finish_non_exhaustive()
+    }
+}
+
+/// Representation of an `Option<T>` whose C representation `P` is a pointer or holds one, with
+/// null standing for `None`.
+///
+/// Seen from C this is just `P`. It is a type of its own so that an `Option` around it, which
+/// has no null left to use, is an `FfiOption` and keeps `Some(None)` apart from `None`.
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct FfiNullable<P>(pub P);
+
+impl<P> Deref for FfiNullable<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P> TaggedOption for FfiNullable<P> {}
+
+/// How `Option<T>` is represented in C, implemented for the `CRepr::C` of `T`.
+///
+/// Pointer-like representations (strings, vecs, raw pointers) use null for `None` and are
+/// otherwise unchanged but for the `FfiNullable` around them; everything else is wrapped in an
+/// `FfiOption`.
+pub trait OptionC: Sized {
+    /// Either `FfiNullable<Self>` or `FfiOption<Self>`.
+    type Repr;
+    /// Whether `Repr` is `FfiNullable<Self>`, with null standing for `None`.
+    const NULLABLE: bool;
+
+    fn some(value: Self) -> Self::Repr;
+    fn none() -> Self::Repr;
+    /// Pointer to the contained value, or `None` if `repr` represents `None`.
+    ///
+    /// # Safety
+    ///
+    /// `repr` must point to a `Self::Repr` made by `some` or `none`, or laid out the same by C.
+    unsafe fn value(repr: *const Self::Repr) -> Option<*const Self>;
+    /// Like `value`, for taking the contained value. Works on the pointers alone, so that the
+    /// result may be written through.
+    ///
+    /// # Safety
+    ///
+    /// As for `value`.
+    unsafe fn value_mut(repr: *mut Self::Repr) -> Option<*mut Self>;
+}
+
+/// Marker for C representations that have no null value of their own and so are represented as
+/// `FfiOption<Self>` inside an `Option`.
+pub trait TaggedOption {}
+
+impl<T: TaggedOption> OptionC for T {
+    type Repr = FfiOption<T>;
+    const NULLABLE: bool = false;
+
+    fn some(value: Self) -> Self::Repr {
+        FfiOption {
+            is_some: true,
+            value: MaybeUninit::new(value),
+        }
+    }
+    fn none() -> Self::Repr {
+        FfiOption {
+            is_some: false,
+            value: MaybeUninit::uninit(),
+        }
+    }
+    unsafe fn value(repr: *const Self::Repr) -> Option<*const Self> {
+        if unsafe { (*repr).is_some } {
+            Some(unsafe { ptr::addr_of!((*repr).value) }.cast())
+        } else {
+            None
+        }
+    }
+    unsafe fn value_mut(repr: *mut Self::Repr) -> Option<*mut Self> {
+        if unsafe { (*repr).is_some } {
+            Some(unsafe { ptr::addr_of_mut!((*repr).value) }.cast())
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> OptionC for *mut T {
+    type Repr = FfiNullable<*mut T>;
+    const NULLABLE: bool = true;
+
+    fn some(value: Self) -> Self::Repr {
+        FfiNullable(value)
+    }
+    fn none() -> Self::Repr {
+        FfiNullable(ptr::null_mut())
+    }
+    unsafe fn value(repr: *const Self::Repr) -> Option<*const Self> {
+        if unsafe { (*repr).0.is_null() } {
+            None
+        } else {
+            Some(repr.cast())
+        }
+    }
+    unsafe fn value_mut(repr: *mut Self::Repr) -> Option<*mut Self> {
+        if unsafe { (*repr).0.is_null() } {
+            None
+        } else {
+            Some(repr.cast())
+        }
+    }
+}
+
+impl<T: CRepr> CRepr for Option<T>
+    where T::C: OptionC
+{
+    type C = <T::C as OptionC>::Repr;
+    type Error = T::Error;
+}
+
+impl<T: FromReprC> FromReprC for Option<T>
+    where T::C: OptionC
+{
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        match <T::C as OptionC>::value_mut(c) {
+            Some(value) => Ok(Some(T::from_repr_c_owned(value)?)),
+            None => Ok(None),
+        }
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        match <T::C as OptionC>::value(c) {
+            Some(value) => Ok(Some(T::from_repr_c_cloned(value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T: IntoReprC> IntoReprC for Option<T>
+    where T::C: OptionC
+{
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        match self {
+            Some(value) => Ok(<T::C as OptionC>::some(value.into_repr_c()?)),
+            None => Ok(<T::C as OptionC>::none()),
+        }
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        if let Some(value) = <T::C as OptionC>::value_mut(c) {
+            T::release_c(value)
+        }
+    }
+}
+
+impl<T: FromReprCBorrowed> FromReprCBorrowed for Option<T>
+    where T::C: OptionC
+{
+    type View<'a> = Option<T::View<'a>> where T: 'a;
+    type SliceView<'a> = Vec<Option<T::View<'a>>> where T: 'a;
+
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+        match <T::C as OptionC>::value(c) {
+            Some(value) => Ok(Some(T::from_repr_c_borrowed(unsafe { &*value })?)),
+            None => Ok(None),
+        }
+    }
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error> {
+        vec::borrowed_each::<Self>(c)
+    }
+}
+
+impl<T: CHeader> CHeader for Option<T>
+    where T::C: OptionC
+{
+    fn c_decl(name: &str) -> String {
+        if <T::C as OptionC>::NULLABLE {
+            T::c_decl(name)
+        } else {
+            format!("{} {}", Self::c_type_name(), name)
+        }
+    }
+    fn c_type_name() -> String {
+        if <T::C as OptionC>::NULLABLE {
+            T::c_type_name()
+        } else {
+            format!("FfiOption_{}", T::c_type_name())
+        }
+    }
+    fn declare(header: &mut Header) {
+        T::declare(header);
+        let name = Self::c_type_name();
+        if !<T::C as OptionC>::NULLABLE && header.begin_struct(&name) {
+            header.add_struct(&name, vec!["bool is_some".to_owned(), T::c_decl("value")]);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::{self, Debug, Formatter};
+use std::mem::{self, ManuallyDrop};
+use std::ops::Deref;
+
+use {FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// Owns the C representation of a `T` and releases it exactly once: when dropped, unless it is
+/// converted back or handed over first.
+///
+/// C representations themselves own their allocations without releasing them on drop, so a
+/// `T::C` kept on the Rust side belongs in an `OwnedFfi`. It only ever holds a valid `T::C` it
+/// owns, so converting it back or viewing it needs no `unsafe`; the only ways to put a value in
+/// other than `new` are the `unsafe` `from_c` and `from_raw`.
+///
+/// Conversions that build several C values one after another also keep each in an `OwnedFfi`
+/// until all of them succeeded, so that an error part way through releases the ones already built.
+pub struct OwnedFfi<T: IntoReprC> {
+    c: ManuallyDrop<T::C>,
+}
+
+impl<T: IntoReprC> OwnedFfi<T> {
+    /// Converts `value`.
+    pub fn new(value: T) -> Result<Self, T::Error> {
+        Ok(OwnedFfi { c: ManuallyDrop::new(value.into_repr_c()?) })
+    }
+
+    /// Takes ownership of `c`, e.g. one handed back by C.
+    ///
+    /// # Safety
+    ///
+    /// `c` must satisfy `IntoReprC::release_c`: valid, with all its allocations made by
+    /// `into_repr_c` and not released since.
+    pub unsafe fn from_c(c: T::C) -> Self {
+        OwnedFfi { c: ManuallyDrop::new(c) }
+    }
+
+    /// Takes back a pointer from `into_raw`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `into_raw` and not have been taken back already. C may have replaced
+    /// the value, as long as the new one satisfies `from_c`.
+    pub unsafe fn from_raw(ptr: *mut T::C) -> Self {
+        OwnedFfi::from_c(*Box::from_raw(ptr))
+    }
+
+    /// Moves the C representation to the heap and hands it over, e.g. for C to hold on to. Only
+    /// `from_raw` releases it again.
+    pub fn into_raw(self) -> *mut T::C {
+        Box::into_raw(Box::new(self.into_c()))
+    }
+
+    /// Gives up ownership of the C representation, e.g. to hand it over to C for good.
+    pub fn into_c(mut self) -> T::C {
+        let c = unsafe { ManuallyDrop::take(&mut self.c) };
+        mem::forget(self);
+        c
+    }
+
+    /// Pointer to the C representation, valid until the `OwnedFfi` is moved or dropped.
+    pub fn as_ptr(&self) -> *const T::C {
+        &*self.c
+    }
+}
+
+impl<T: FromReprC + IntoReprC> OwnedFfi<T> {
+    /// Converts back, consuming the C representation.
+    pub fn into_rust(self) -> Result<T, T::Error> {
+        let mut c = self.into_c();
+        unsafe { T::from_repr_c_owned(&mut c) }
+    }
+
+    /// Converts a copy back, leaving the C representation as is.
+    pub fn to_rust(&self) -> Result<T, T::Error> {
+        unsafe { T::from_repr_c_cloned(&*self.c) }
+    }
+}
+
+impl<T: FromReprCBorrowed + IntoReprC> OwnedFfi<T> {
+    /// Views the C representation without converting it.
+    pub fn view(&self) -> Result<T::View<'_>, T::Error> {
+        unsafe { T::from_repr_c_borrowed(&self.c) }
+    }
+}
+
+impl<T: IntoReprC> Deref for OwnedFfi<T> {
+    type Target = T::C;
+
+    fn deref(&self) -> &T::C {
+        &self.c
+    }
+}
+
+impl<T: IntoReprC> Debug for OwnedFfi<T>
+    where T::C: Debug
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_tuple("OwnedFfi").field(&*self.c).finish()
+    }
+}
+
+impl<T: IntoReprC> Drop for OwnedFfi<T> {
+    fn drop(&mut self) {
+        unsafe { T::release_c(&mut *self.c) };
+    }
+}
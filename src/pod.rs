@@ -0,0 +1,112 @@
+/// Plain-old-data types which are their own C representation.
+///
+/// `impl_pod_repr_c!` implements `ReprC` for `FfiPod` types as a bitwise copy, and a `Vec` of them
+/// hands its buffer to C and back as is instead of converting element by element. All primitive
+/// integers and floats are `FfiPod`, as are arrays of `FfiPod` types; those take their `ReprC`
+/// from the one of every array, which keeps a `Vec` of them a buffer handover as well. A user
+/// struct can be made `FfiPod` with
+///
+/// ```
+/// # use ffi_trait_poc::FfiPod;
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// pub struct Point {
+///     pub x: f64,
+///     pub y: f64,
+/// }
+///
+/// unsafe impl FfiPod for Point {}
+/// ffi_trait_poc::impl_pod_repr_c!(Point);
+/// ```
+///
+/// # Safety
+///
+/// The type must have a layout C can describe (a primitive, or `#[repr(C)]` with `FfiPod` fields or
+/// arrays of them), must not own or borrow anything, and every bit pattern C could store in it
+/// must be a valid value. `bool` and `char` for instance are not `FfiPod`.
+pub unsafe trait FfiPod: Copy {}
+
+unsafe impl<T: FfiPod, const N: usize> FfiPod for [T; N] {}
+
+/// Implements `CRepr`, `FromReprC`, `IntoReprC`, `FromReprCBorrowed` and `TaggedOption` for the
+/// given `FfiPod` types.
+///
+/// A blanket `impl<T: FfiPod> CRepr for T` would overlap with `impl<T: CRepr> CRepr for Box<T>`,
+/// which the compiler rejects: `Box` is `#[fundamental]`, so any crate could implement `FfiPod`
+/// for a `Box<TheirType>` of its own. Hence every `FfiPod` type spells out its impls through this
+/// macro, except arrays, which have generic impls of their own.
+#[macro_export]
+macro_rules! impl_pod_repr_c {
+    ($($ty:ty),* $(,)*) => {
+        $(
+            impl $crate::CRepr for $ty
+                where $ty: $crate::FfiPod
+            {
+                type C = $ty;
+                type Error = ::std::convert::Infallible;
+            }
+
+            impl $crate::FromReprC for $ty
+                where $ty: $crate::FfiPod
+            {
+                unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                    Ok(unsafe { *c })
+                }
+                unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                    Ok(unsafe { *c })
+                }
+                unsafe fn vec_from_repr_c_owned(c: *mut $crate::FfiVec<Self>)
+                                                -> Result<Vec<Self>, Self::Error> {
+                    Ok(unsafe { ::std::ptr::read(c).into_vec() })
+                }
+                unsafe fn vec_from_repr_c_cloned(c: *const $crate::FfiVec<Self>)
+                                                 -> Result<Vec<Self>, Self::Error> {
+                    Ok(unsafe { (*c).as_slice() }.to_vec())
+                }
+                unsafe fn array_vec_from_repr_c_owned<const N: usize>(
+                    c: *mut $crate::FfiVec<[Self; N]>)
+                    -> Result<Vec<[Self; N]>, Self::Error> {
+                    Ok(unsafe { ::std::ptr::read(c).into_vec() })
+                }
+                unsafe fn array_vec_from_repr_c_cloned<const N: usize>(
+                    c: *const $crate::FfiVec<[Self; N]>)
+                    -> Result<Vec<[Self; N]>, Self::Error> {
+                    Ok(unsafe { (*c).as_slice() }.to_vec())
+                }
+            }
+
+            impl $crate::IntoReprC for $ty
+                where $ty: $crate::FfiPod
+            {
+                fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                    Ok(self)
+                }
+                unsafe fn release_c(_c: *mut Self::C) {}
+                fn vec_into_repr_c(v: Vec<Self>) -> Result<$crate::FfiVec<Self::C>, Self::Error> {
+                    Ok($crate::FfiVec::from_vec(v))
+                }
+                fn array_vec_into_repr_c<const N: usize>(v: Vec<[Self; N]>)
+                    -> Result<$crate::FfiVec<[Self; N]>, Self::Error> {
+                    Ok($crate::FfiVec::from_vec(v))
+                }
+            }
+
+            impl $crate::FromReprCBorrowed for $ty
+                where $ty: $crate::FfiPod
+            {
+                type View<'a> = $ty;
+                type SliceView<'a> = &'a [$ty];
+
+                unsafe fn from_repr_c_borrowed(c: &$ty) -> Result<$ty, Self::Error> {
+                    Ok(*c)
+                }
+                unsafe fn slice_from_repr_c_borrowed(c: &$crate::FfiVec<$ty>)
+                                                     -> Result<&[$ty], Self::Error> {
+                    Ok(unsafe { c.as_slice() })
+                }
+            }
+
+            impl $crate::TaggedOption for $ty where $ty: $crate::FfiPod {}
+        )*
+    }
+}
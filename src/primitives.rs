@@ -0,0 +1,206 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use error::{self, ErrorPath, FieldPath};
+use header::CHeader;
+use impl_pod_repr_c;
+use pod::FfiPod;
+use result::ErrorCode;
+use vec::FfiVec;
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// A value coming from C that is out of range for the Rust type it converts to, and where.
+#[derive(Debug)]
+pub struct PrimitiveError {
+    pub kind: PrimitiveErrorKind,
+    pub path: FieldPath,
+}
+
+#[derive(Debug)]
+pub enum PrimitiveErrorKind {
+    /// A `bool` other than 0 or 1.
+    InvalidBool(u8),
+    /// A `char` that is not a Unicode scalar value.
+    InvalidChar(u32),
+}
+
+impl From<PrimitiveErrorKind> for PrimitiveError {
+    fn from(kind: PrimitiveErrorKind) -> Self {
+        PrimitiveError {
+            kind,
+            path: FieldPath::default(),
+        }
+    }
+}
+
+impl ErrorPath for PrimitiveError {
+    fn path(&self) -> &FieldPath {
+        &self.path
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        &mut self.path
+    }
+}
+
+impl ErrorCode for PrimitiveError {
+    fn error_code(&self) -> i32 {
+        match self.kind {
+            PrimitiveErrorKind::InvalidBool(_) => 4,
+            PrimitiveErrorKind::InvalidChar(_) => 5,
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("InvalidBool", 4), ("InvalidChar", 5)]
+    }
+}
+
+impl Display for PrimitiveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        error::write_path(f, &self.path)?;
+        match self.kind {
+            PrimitiveErrorKind::InvalidBool(v) => write!(f, "invalid bool {}, expected 0 or 1", v),
+            PrimitiveErrorKind::InvalidChar(v) => write!(f, "invalid char {:#x}", v),
+        }
+    }
+}
+
+impl Error for PrimitiveError {}
+
+macro_rules! impl_pod {
+    ($($ty:ty => $c_name:expr),* $(,)*) => {
+        $(
+            unsafe impl FfiPod for $ty {}
+            impl_pod_repr_c!($ty);
+
+            impl CHeader for $ty {
+                fn c_decl(name: &str) -> String {
+                    format!("{} {}", $c_name, name)
+                }
+                fn c_type_name() -> String {
+                    $c_name.to_owned()
+                }
+            }
+        )*
+    }
+}
+
+impl_pod! {
+    i8 => "int8_t",
+    i16 => "int16_t",
+    i32 => "int32_t",
+    i64 => "int64_t",
+    u16 => "uint16_t",
+    u32 => "uint32_t",
+    u64 => "uint64_t",
+    isize => "intptr_t",
+    usize => "uintptr_t",
+    f32 => "float",
+    f64 => "double",
+}
+
+unsafe impl FfiPod for u8 {}
+impl_pod_repr_c!(u8);
+
+// `Vec<u8>` keeps the dedicated `FfiBytes` name.
+impl CHeader for u8 {
+    fn c_decl(name: &str) -> String {
+        format!("uint8_t {}", name)
+    }
+    fn c_type_name() -> String {
+        "uint8_t".to_owned()
+    }
+    fn vec_c_type_name() -> String {
+        "FfiBytes".to_owned()
+    }
+}
+
+// -----------------
+
+impl CRepr for bool {
+    type C = u8;
+    type Error = PrimitiveError;
+}
+
+impl FromReprC for bool {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        Self::from_repr_c_cloned(c)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        match unsafe { *c } {
+            0 => Ok(false),
+            1 => Ok(true),
+            v => Err(PrimitiveErrorKind::InvalidBool(v).into()),
+        }
+    }
+}
+
+impl IntoReprC for bool {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        Ok(self as u8)
+    }
+    unsafe fn release_c(_c: *mut Self::C) {}
+}
+
+impl FromReprCBorrowed for bool {
+    type View<'a> = bool;
+    type SliceView<'a> = Vec<bool>;
+
+    unsafe fn from_repr_c_borrowed(c: &Self::C) -> Result<bool, Self::Error> {
+        Self::from_repr_c_cloned(c)
+    }
+    unsafe fn slice_from_repr_c_borrowed(c: &FfiVec<Self::C>) -> Result<Vec<bool>, Self::Error> {
+        Self::vec_from_repr_c_cloned(c)
+    }
+}
+
+impl CHeader for bool {
+    fn c_decl(name: &str) -> String {
+        format!("uint8_t {}", name)
+    }
+    fn c_type_name() -> String {
+        "bool".to_owned()
+    }
+}
+
+impl CRepr for char {
+    type C = u32;
+    type Error = PrimitiveError;
+}
+
+impl FromReprC for char {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        Self::from_repr_c_cloned(c)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        let v = unsafe { *c };
+        ::std::char::from_u32(v).ok_or_else(|| PrimitiveErrorKind::InvalidChar(v).into())
+    }
+}
+
+impl IntoReprC for char {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        Ok(self as u32)
+    }
+    unsafe fn release_c(_c: *mut Self::C) {}
+}
+
+impl FromReprCBorrowed for char {
+    type View<'a> = char;
+    type SliceView<'a> = Vec<char>;
+
+    unsafe fn from_repr_c_borrowed(c: &Self::C) -> Result<char, Self::Error> {
+        Self::from_repr_c_cloned(c)
+    }
+    unsafe fn slice_from_repr_c_borrowed(c: &FfiVec<Self::C>) -> Result<Vec<char>, Self::Error> {
+        Self::vec_from_repr_c_cloned(c)
+    }
+}
+
+impl CHeader for char {
+    fn c_decl(name: &str) -> String {
+        format!("uint32_t {}", name)
+    }
+    fn c_type_name() -> String {
+        "char".to_owned()
+    }
+}
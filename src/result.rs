@@ -0,0 +1,96 @@
+use std::convert::Infallible;
+use std::ffi::{CStr, CString};
+use std::fmt::Display;
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Errors with a stable numeric code the frontend can act on.
+///
+/// 0 means success and is never an error's code. Codes below 1000 are reserved for this crate;
+/// the error types of a crate using it pick theirs from 1000 up, or hand out the codes of the
+/// errors they wrap.
+pub trait ErrorCode {
+    fn error_code(&self) -> i32;
+
+    /// Name and value of every code `error_code` may return, declared in the header as
+    /// `FfiErrorCode_<name>`.
+    fn error_codes() -> Vec<(&'static str, i32)> where Self: Sized;
+}
+
+impl ErrorCode for Infallible {
+    fn error_code(&self) -> i32 {
+        match *self {}
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        Vec::new()
+    }
+}
+
+/// `#[repr(C)]` outcome of an operation, as handed to the frontend.
+///
+/// `error_code` is 0 on success, in which case `description` is null. Otherwise `description` is
+/// a nul terminated message owned by the `FfiResult`, valid for as long as the frontend is
+/// allowed to look at the result. Rust code only reads the fields, through accessors, so that it
+/// cannot point `description` at memory that `Drop` must not free.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiResult {
+    error_code: i32,
+    description: *const c_char,
+}
+
+impl FfiResult {
+    pub fn ok() -> Self {
+        FfiResult {
+            error_code: 0,
+            description: ptr::null(),
+        }
+    }
+
+    /// Describes `e` with its code and its `Display` output.
+    pub fn from_error<E: ErrorCode + Display>(e: &E) -> Self {
+        FfiResult {
+            error_code: e.error_code(),
+            description: describe(e).into_raw(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error_code == 0
+    }
+
+    pub fn error_code(&self) -> i32 {
+        self.error_code
+    }
+
+    pub fn description(&self) -> Option<&CStr> {
+        if self.description.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(self.description) })
+        }
+    }
+}
+
+impl<T, E: ErrorCode + Display> From<Result<T, E>> for FfiResult {
+    fn from(res: Result<T, E>) -> Self {
+        match res {
+            Ok(_) => FfiResult::ok(),
+            Err(ref e) => FfiResult::from_error(e),
+        }
+    }
+}
+
+/// The `Display` output of `e` as a C string.
+pub(crate) fn describe<E: Display>(e: &E) -> CString {
+    // A nul in the message would cut it short, so spell it out instead.
+    CString::new(e.to_string().replace('\0', "\\0")).unwrap_or_default()
+}
+
+impl Drop for FfiResult {
+    fn drop(&mut self) {
+        if !self.description.is_null() {
+            let _ = unsafe { CString::from_raw(self.description as *mut c_char) };
+        }
+    }
+}
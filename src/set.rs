@@ -0,0 +1,144 @@
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hash;
+
+use error::{ErrorPath, FieldPath};
+use header::{CHeader, Header};
+use map::DuplicateKey;
+use result::ErrorCode;
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// Error converting a set, from one of its elements or a duplicate element.
+#[derive(Debug)]
+pub enum SetError<E> {
+    Element(E),
+    DuplicateKey(DuplicateKey),
+}
+
+impl<E: ErrorPath> ErrorPath for SetError<E> {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            SetError::Element(ref e) => e.path(),
+            SetError::DuplicateKey(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            SetError::Element(ref mut e) => e.path_mut(),
+            SetError::DuplicateKey(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+impl<E: ErrorCode> ErrorCode for SetError<E> {
+    fn error_code(&self) -> i32 {
+        match *self {
+            SetError::Element(ref e) => e.error_code(),
+            SetError::DuplicateKey(ref e) => e.error_code(),
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        let mut codes = E::error_codes();
+        codes.extend(DuplicateKey::error_codes());
+        codes
+    }
+}
+
+impl<E: Display> Display for SetError<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            SetError::Element(ref e) => e.fmt(f),
+            SetError::DuplicateKey(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: Error> Error for SetError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SetError::Element(ref e) => e.source(),
+            SetError::DuplicateKey(ref e) => e.source(),
+        }
+    }
+}
+
+// -----------------
+
+/// Builds a set from the elements converted from C, failing on the first repeated one.
+fn collect_elements<T, S>(elements: Vec<T>, mut set: S, insert: fn(&mut S, T) -> bool)
+                          -> Result<S, SetError<T::Error>>
+    where T: CRepr
+{
+    for (i, elt) in elements.into_iter().enumerate() {
+        if !insert(&mut set, elt) {
+            let e = DuplicateKey { path: FieldPath::default() };
+            return Err(SetError::DuplicateKey(e).at_index(i));
+        }
+    }
+    Ok(set)
+}
+
+// Sets are represented like a `Vec` of their elements, in iteration order, so sorted for a
+// `BTreeSet`. Their elements are converted straight into and out of the `FfiVec`.
+macro_rules! impl_set {
+    ($set:ident, $new:expr, $($bound:tt)+) => {
+        impl<T: CRepr> CRepr for $set<T> {
+            type C = FfiVec<T::C>;
+            type Error = SetError<T::Error>;
+        }
+
+        impl<T: FromReprC + $($bound)+> FromReprC for $set<T> {
+            unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                let elements = Vec::<T>::from_repr_c_owned(c).map_err(SetError::Element)?;
+                let set = $new(elements.len());
+                collect_elements(elements, set, $set::insert)
+            }
+            unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                let elements = Vec::<T>::from_repr_c_cloned(c).map_err(SetError::Element)?;
+                let set = $new(elements.len());
+                collect_elements(elements, set, $set::insert)
+            }
+        }
+
+        impl<T: IntoReprC + $($bound)+> IntoReprC for $set<T> {
+            fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                vec::into_repr_c_each(self).map_err(SetError::Element)
+            }
+            unsafe fn release_c(c: *mut Self::C) {
+                Vec::<T>::release_c(c)
+            }
+        }
+
+        /// The view lists the elements as they are, without checking them for duplicates.
+        impl<T: FromReprCBorrowed + $($bound)+> FromReprCBorrowed for $set<T> {
+            type View<'a> = T::SliceView<'a> where T: 'a;
+            type SliceView<'a> = Vec<T::SliceView<'a>> where T: 'a;
+
+            unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C)
+                                               -> Result<Self::View<'a>, Self::Error> {
+                T::slice_from_repr_c_borrowed(c).map_err(SetError::Element)
+            }
+            unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                                     -> Result<Self::SliceView<'a>, Self::Error> {
+                vec::borrowed_each::<Self>(c)
+            }
+        }
+
+        impl<T: CHeader> CHeader for $set<T> {
+            fn c_decl(name: &str) -> String {
+                Vec::<T>::c_decl(name)
+            }
+            fn c_type_name() -> String {
+                Vec::<T>::c_type_name()
+            }
+            fn declare(header: &mut Header) {
+                Vec::<T>::declare(header)
+            }
+        }
+    }
+}
+
+impl_set!(HashSet, HashSet::with_capacity, Eq + Hash);
+impl_set!(BTreeSet, |_| BTreeSet::new(), Ord);
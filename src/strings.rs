@@ -0,0 +1,155 @@
+use std::error::Error;
+use std::ffi::{CStr, CString, IntoStringError, NulError};
+use std::fmt::{self, Display, Formatter};
+use std::os::raw::c_char;
+use std::str::Utf8Error;
+
+use error::{self, ErrorPath, FieldPath};
+use header::CHeader;
+use result::ErrorCode;
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// A `String` that failed to convert, and where.
+#[derive(Debug)]
+pub struct StringError {
+    pub kind: StringErrorKind,
+    pub path: FieldPath,
+}
+
+#[derive(Debug)]
+pub enum StringErrorKind {
+    Utf8(Utf8Error),
+    Null(NulError),
+    IntoString(IntoStringError),
+    /// A null pointer was given where a string was required. Use `Option<String>` for strings
+    /// that may be absent.
+    NullPointer,
+}
+
+impl From<StringErrorKind> for StringError {
+    fn from(kind: StringErrorKind) -> Self {
+        StringError {
+            kind,
+            path: FieldPath::default(),
+        }
+    }
+}
+
+impl From<Utf8Error> for StringError {
+    fn from(e: Utf8Error) -> Self {
+        StringErrorKind::Utf8(e).into()
+    }
+}
+
+impl From<NulError> for StringError {
+    fn from(e: NulError) -> Self {
+        StringErrorKind::Null(e).into()
+    }
+}
+
+impl From<IntoStringError> for StringError {
+    fn from(e: IntoStringError) -> Self {
+        StringErrorKind::IntoString(e).into()
+    }
+}
+
+impl ErrorPath for StringError {
+    fn path(&self) -> &FieldPath {
+        &self.path
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        &mut self.path
+    }
+}
+
+impl ErrorCode for StringError {
+    fn error_code(&self) -> i32 {
+        match self.kind {
+            StringErrorKind::Utf8(_) | StringErrorKind::IntoString(_) => 1,
+            StringErrorKind::Null(_) => 2,
+            StringErrorKind::NullPointer => 3,
+        }
+    }
+    fn error_codes() -> Vec<(&'static str, i32)> {
+        vec![("InvalidUtf8", 1), ("InteriorNul", 2), ("NullPointer", 3)]
+    }
+}
+
+impl Display for StringError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        error::write_path(f, &self.path)?;
+        match self.kind {
+            StringErrorKind::Utf8(ref e) => e.fmt(f),
+            StringErrorKind::Null(ref e) => e.fmt(f),
+            StringErrorKind::IntoString(ref e) => e.fmt(f),
+            StringErrorKind::NullPointer => f.write_str("null pointer where a string was required"),
+        }
+    }
+}
+
+impl Error for StringError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            StringErrorKind::Utf8(ref e) => Some(e),
+            StringErrorKind::Null(ref e) => Some(e),
+            StringErrorKind::IntoString(ref e) => Some(e),
+            StringErrorKind::NullPointer => None,
+        }
+    }
+}
+
+impl CRepr for String {
+    type C = *mut c_char;
+    type Error = StringError;
+}
+
+impl FromReprC for String {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        if unsafe { (*c).is_null() } {
+            return Err(StringErrorKind::NullPointer.into());
+        }
+        Ok(unsafe { CString::from_raw(*c) }.into_string()?)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        if unsafe { (*c).is_null() } {
+            return Err(StringErrorKind::NullPointer.into());
+        }
+        Ok(unsafe { CStr::from_ptr(*c) }.to_str()?.to_owned())
+    }
+}
+
+impl IntoReprC for String {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        Ok(CString::new(self)?.into_raw())
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        if unsafe { !(*c).is_null() } {
+            drop(unsafe { CString::from_raw(*c) });
+        }
+    }
+}
+
+impl FromReprCBorrowed for String {
+    type View<'a> = &'a str;
+    type SliceView<'a> = Vec<&'a str>;
+
+    unsafe fn from_repr_c_borrowed(c: &Self::C) -> Result<&str, Self::Error> {
+        if c.is_null() {
+            return Err(StringErrorKind::NullPointer.into());
+        }
+        Ok(unsafe { CStr::from_ptr(*c) }.to_str()?)
+    }
+    unsafe fn slice_from_repr_c_borrowed(c: &FfiVec<Self::C>) -> Result<Vec<&str>, Self::Error> {
+        vec::borrowed_each::<Self>(c)
+    }
+}
+
+impl CHeader for String {
+    fn c_decl(name: &str) -> String {
+        format!("char *{}", name)
+    }
+    fn c_type_name() -> String {
+        "String".to_owned()
+    }
+}
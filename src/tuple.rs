@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use error::{ErrorPath, FieldPath};
+use header::{CHeader, Header};
+use option::TaggedOption;
+use owned::OwnedFfi;
+use result::ErrorCode;
+use vec::{self, FfiVec};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+// A tuple `(A, B, ...)` is represented as `FfiTupleN<A::C, B::C, ...>`, a struct with the
+// elements as fields `_0`, `_1`, ..., declared in C as `FfiTupleN_<A>_<B>...`. Converting fails
+// with `TupleErrorN`, telling which element failed.
+
+macro_rules! impl_tuple {
+    ($ffi:ident, $error:ident, $($field:ident: $ty:ident => $variant:ident),+) => {
+        /// `#[repr(C)]` representation of a tuple, with its elements as fields `_0`, `_1`, ...
+        #[repr(C)]
+        #[derive(Debug)]
+        pub struct $ffi<$($ty),+> {
+            $(pub $field: $ty),+
+        }
+
+        /// Error converting a tuple, from the element of the variant's index.
+        #[derive(Debug)]
+        pub enum $error<$($ty),+> {
+            $($variant($ty)),+
+        }
+
+        impl<$($ty: ErrorPath),+> ErrorPath for $error<$($ty),+> {
+            fn path(&self) -> &FieldPath {
+                match *self {
+                    $($error::$variant(ref e) => e.path()),+
+                }
+            }
+            fn path_mut(&mut self) -> &mut FieldPath {
+                match *self {
+                    $($error::$variant(ref mut e) => e.path_mut()),+
+                }
+            }
+        }
+
+        impl<$($ty: ErrorCode),+> ErrorCode for $error<$($ty),+> {
+            fn error_code(&self) -> i32 {
+                match *self {
+                    $($error::$variant(ref e) => e.error_code()),+
+                }
+            }
+            fn error_codes() -> Vec<(&'static str, i32)> {
+                let mut codes = Vec::new();
+                $(codes.extend($ty::error_codes());)+
+                codes
+            }
+        }
+
+        impl<$($ty: Display),+> Display for $error<$($ty),+> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                match *self {
+                    $($error::$variant(ref e) => e.fmt(f)),+
+                }
+            }
+        }
+
+        impl<$($ty: Error),+> Error for $error<$($ty),+> {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                match *self {
+                    $($error::$variant(ref e) => e.source()),+
+                }
+            }
+        }
+
+        impl<$($ty: CRepr),+> CRepr for ($($ty,)+) {
+            type C = $ffi<$($ty::C),+>;
+            type Error = $error<$($ty::Error),+>;
+        }
+
+        impl<$($ty: FromReprC),+> FromReprC for ($($ty,)+) {
+            unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+                let c = unsafe { &mut *c };
+                // Take every element before returning an error so that none is left unreleased.
+                $(let $field = $ty::from_repr_c_owned(&mut c.$field);)+
+                Ok(($($field.map_err(|e| $error::$variant(e.in_field(stringify!($field))))?,)+))
+            }
+            unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+                let c = unsafe { &*c };
+                Ok(($($ty::from_repr_c_cloned(&c.$field)
+                        .map_err(|e| $error::$variant(e.in_field(stringify!($field))))?,)+))
+            }
+        }
+
+        impl<$($ty: IntoReprC),+> IntoReprC for ($($ty,)+) {
+            fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+                let ($($field,)+) = self;
+                $(let $field = OwnedFfi::new($field)
+                    .map_err(|e| $error::$variant(e.in_field(stringify!($field))))?;)+
+                Ok($ffi { $($field: $field.into_c()),+ })
+            }
+            unsafe fn release_c(c: *mut Self::C) {
+                $(unsafe { $ty::release_c(&mut (*c).$field) };)+
+            }
+        }
+
+        impl<$($ty: FromReprCBorrowed),+> FromReprCBorrowed for ($($ty,)+) {
+            type View<'a> = ($($ty::View<'a>,)+) where $($ty: 'a),+;
+            type SliceView<'a> = Vec<Self::View<'a>> where $($ty: 'a),+;
+
+            unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C)
+                                               -> Result<Self::View<'a>, Self::Error> {
+                Ok(($($ty::from_repr_c_borrowed(&c.$field)
+                        .map_err(|e| $error::$variant(e.in_field(stringify!($field))))?,)+))
+            }
+            unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                                     -> Result<Self::SliceView<'a>, Self::Error> {
+                vec::borrowed_each::<Self>(c)
+            }
+        }
+
+        impl<$($ty: CHeader),+> CHeader for ($($ty,)+) {
+            fn c_decl(name: &str) -> String {
+                format!("{} {}", Self::c_type_name(), name)
+            }
+            fn c_type_name() -> String {
+                let mut name = stringify!($ffi).to_owned();
+                $(name.push('_');
+                  name.push_str(&$ty::c_type_name());)+
+                name
+            }
+            fn declare(header: &mut Header) {
+                let name = Self::c_type_name();
+                if header.begin_struct(&name) {
+                    $($ty::declare(header);)+
+                    header.add_struct(&name, vec![$($ty::c_decl(stringify!($field))),+]);
+                }
+            }
+        }
+
+        impl<$($ty),+> TaggedOption for $ffi<$($ty),+> {}
+    }
+}
+
+impl_tuple!(FfiTuple2, TupleError2, _0: A => Field0, _1: B => Field1);
+impl_tuple!(FfiTuple3, TupleError3, _0: A => Field0, _1: B => Field1, _2: C => Field2);
+impl_tuple!(FfiTuple4, TupleError4,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3);
+impl_tuple!(FfiTuple5, TupleError5,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4);
+impl_tuple!(FfiTuple6, TupleError6,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5);
+impl_tuple!(FfiTuple7, TupleError7,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5, _6: G => Field6);
+impl_tuple!(FfiTuple8, TupleError8,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5, _6: G => Field6, _7: H => Field7);
+impl_tuple!(FfiTuple9, TupleError9,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5, _6: G => Field6, _7: H => Field7, _8: I => Field8);
+impl_tuple!(FfiTuple10, TupleError10,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5, _6: G => Field6, _7: H => Field7, _8: I => Field8, _9: J => Field9);
+impl_tuple!(FfiTuple11, TupleError11,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5, _6: G => Field6, _7: H => Field7, _8: I => Field8, _9: J => Field9,
+            _10: K => Field10);
+impl_tuple!(FfiTuple12, TupleError12,
+            _0: A => Field0, _1: B => Field1, _2: C => Field2, _3: D => Field3, _4: E => Field4,
+            _5: F => Field5, _6: G => Field6, _7: H => Field7, _8: I => Field8, _9: J => Field9,
+            _10: K => Field10, _11: L => Field11);
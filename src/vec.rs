@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::ptr;
+
+use error::ErrorPath;
+use header::{CHeader, Header};
+use option::{FfiNullable, OptionC};
+use {CRepr, FromReprC, FromReprCBorrowed, IntoReprC};
+
+/// `#[repr(C)]` representation of a `Vec<T>`, embeddable as a single field in Ffi structs.
+///
+/// Seen from C this is `struct { T *ptr; size_t len; size_t cap; }`, in that order. `ptr` points
+/// at `len` initialised elements of an allocation made by Rust for `cap` elements; `cap` must be
+/// handed back unchanged for Rust to release the buffer. C may also write an empty vec as
+/// `{NULL, 0, 0}`, which owns nothing.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiVec<T> {
+    pub ptr: *mut T,
+    pub len: usize,
+    pub cap: usize,
+}
+
+/// `#[repr(C)]` representation of a `Vec<u8>`.
+pub type FfiBytes = FfiVec<u8>;
+
+impl<T> FfiVec<T> {
+    /// Gives up ownership of the buffer of `v`.
+    pub fn from_vec(mut v: Vec<T>) -> Self {
+        let (ptr, len, cap) = (v.as_mut_ptr(), v.len(), v.capacity());
+        mem::forget(v);
+        FfiVec { ptr, len, cap }
+    }
+
+    /// Takes back ownership of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// `self` must have been produced by `from_vec` and not been reclaimed already, or have a null
+    /// `ptr` and a `len` of 0.
+    pub unsafe fn into_vec(self) -> Vec<T> {
+        if self.ptr.is_null() {
+            return Vec::new();
+        }
+        Vec::from_raw_parts(self.ptr, self.len, self.cap)
+    }
+
+    /// Borrows the elements.
+    ///
+    /// # Safety
+    ///
+    /// `self` must describe a live buffer of `len` initialised elements, or have a null `ptr` and a
+    /// `len` of 0.
+    pub unsafe fn as_slice(&self) -> &[T] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        std::slice::from_raw_parts(self.ptr, self.len)
+    }
+}
+
+// `from_vec` never yields a null `ptr`, not even for empty vectors, so null is free to mean `None`.
+impl<T> OptionC for FfiVec<T> {
+    type Repr = FfiNullable<FfiVec<T>>;
+    const NULLABLE: bool = true;
+
+    fn some(value: Self) -> Self::Repr {
+        FfiNullable(value)
+    }
+    fn none() -> Self::Repr {
+        FfiNullable(FfiVec {
+            ptr: ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        })
+    }
+    unsafe fn value(repr: *const Self::Repr) -> Option<*const Self> {
+        if unsafe { (*repr).0.ptr.is_null() } {
+            None
+        } else {
+            Some(repr.cast())
+        }
+    }
+
+    unsafe fn value_mut(repr: *mut Self::Repr) -> Option<*mut Self> {
+        if unsafe { (*repr).0.ptr.is_null() } {
+            None
+        } else {
+            Some(repr.cast())
+        }
+    }
+}
+
+/// Element by element conversion behind the default `FromReprC::vec_from_repr_c_owned`.
+///
+/// Every element is released even if an earlier one fails to convert.
+///
+/// # Safety
+///
+/// As for `FromReprC::from_repr_c_owned`.
+pub unsafe fn from_repr_c_owned_each<T: FromReprC>(c: *mut FfiVec<T::C>)
+                                                  -> Result<Vec<T>, T::Error> {
+    let mut v_ffi = unsafe { ptr::read(c).into_vec() }.into_iter();
+    let mut v = Vec::with_capacity(v_ffi.len());
+    for (i, mut elt) in (&mut v_ffi).enumerate() {
+        match unsafe { T::from_repr_c_owned(&mut elt) } {
+            Ok(elt) => v.push(elt),
+            Err(e) => {
+                for mut elt in v_ffi {
+                    let _ = unsafe { T::from_repr_c_owned(&mut elt) };
+                }
+                return Err(e.at_index(i));
+            }
+        }
+    }
+    Ok(v)
+}
+
+/// Element by element conversion behind the default `FromReprC::vec_from_repr_c_cloned`.
+///
+/// # Safety
+///
+/// As for `FromReprC::from_repr_c_cloned`.
+pub unsafe fn from_repr_c_cloned_each<T: FromReprC>(c: *const FfiVec<T::C>)
+                                                   -> Result<Vec<T>, T::Error> {
+    let slice_ffi = unsafe { (*c).as_slice() };
+    let mut v = Vec::with_capacity(slice_ffi.len());
+    for (i, elt) in slice_ffi.iter().enumerate() {
+        v.push(unsafe { T::from_repr_c_cloned(elt) }.map_err(|e| e.at_index(i))?);
+    }
+    Ok(v)
+}
+
+/// `Vec` of the views of the elements, for `FromReprCBorrowed::slice_from_repr_c_borrowed`.
+///
+/// # Safety
+///
+/// As for `FromReprCBorrowed::from_repr_c_borrowed`.
+pub unsafe fn borrowed_each<T: FromReprCBorrowed>(c: &FfiVec<T::C>)
+                                                 -> Result<Vec<T::View<'_>>, T::Error> {
+    let slice_ffi = unsafe { c.as_slice() };
+    slice_ffi.iter()
+        .enumerate()
+        .map(|(i, elt)| unsafe { T::from_repr_c_borrowed(elt) }.map_err(|e| e.at_index(i)))
+        .collect()
+}
+
+/// Element by element conversion behind the default `IntoReprC::vec_into_repr_c`, also taking
+/// the elements of other collections straight into the `FfiVec`.
+///
+/// The elements converted so far are released again if one fails to convert.
+pub fn into_repr_c_each<T, I>(v: I) -> Result<FfiVec<T::C>, T::Error>
+    where T: IntoReprC,
+          I: IntoIterator<Item = T>,
+          I::IntoIter: ExactSizeIterator
+{
+    let v = v.into_iter();
+    let mut v_ffi = Vec::with_capacity(v.len());
+    for (i, elt) in v.enumerate() {
+        match elt.into_repr_c() {
+            Ok(new_elt) => v_ffi.push(new_elt),
+            Err(e) => {
+                release_each::<T>(v_ffi);
+                return Err(e.at_index(i));
+            }
+        }
+    }
+    Ok(FfiVec::from_vec(v_ffi))
+}
+
+// The elements must satisfy `IntoReprC::release_c`.
+fn release_each<T: IntoReprC>(v_ffi: Vec<T::C>) {
+    for mut elt in v_ffi {
+        unsafe { T::release_c(&mut elt) };
+    }
+}
+
+impl<T: CRepr> CRepr for Vec<T> {
+    type C = FfiVec<T::C>;
+    type Error = T::Error;
+}
+
+impl<T: FromReprC> FromReprC for Vec<T> {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        T::vec_from_repr_c_owned(c)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        T::vec_from_repr_c_cloned(c)
+    }
+}
+
+impl<T: IntoReprC> IntoReprC for Vec<T> {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        T::vec_into_repr_c(self)
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        release_each::<T>(unsafe { ptr::read(c).into_vec() })
+    }
+}
+
+impl<T: FromReprCBorrowed> FromReprCBorrowed for Vec<T> {
+    type View<'a> = T::SliceView<'a> where T: 'a;
+    type SliceView<'a> = Vec<T::SliceView<'a>> where T: 'a;
+
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+        T::slice_from_repr_c_borrowed(c)
+    }
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error> {
+        borrowed_each::<Self>(c)
+    }
+}
+
+impl<T: CHeader> CHeader for Vec<T> {
+    fn c_decl(name: &str) -> String {
+        format!("{} {}", Self::c_type_name(), name)
+    }
+    fn c_type_name() -> String {
+        T::vec_c_type_name()
+    }
+    fn declare(header: &mut Header) {
+        let name = Self::c_type_name();
+        if header.begin_struct(&name) {
+            T::declare(header);
+            header.add_struct(&name,
+                              vec![T::c_decl("*ptr"),
+                                   "size_t len".to_owned(),
+                                   "size_t cap".to_owned()]);
+        }
+    }
+}
+
+// -----------------
+
+// `VecDeque` and `Vec` convert into each other in place, so a deque goes through `Vec` and keeps
+// its buffer whenever `Vec<T>` does, e.g. for `FfiPod` elements.
+
+impl<T: CRepr> CRepr for VecDeque<T> {
+    type C = FfiVec<T::C>;
+    type Error = T::Error;
+}
+
+impl<T: FromReprC> FromReprC for VecDeque<T> {
+    unsafe fn from_repr_c_owned(c: *mut Self::C) -> Result<Self, Self::Error> {
+        Vec::from_repr_c_owned(c).map(VecDeque::from)
+    }
+    unsafe fn from_repr_c_cloned(c: *const Self::C) -> Result<Self, Self::Error> {
+        Vec::from_repr_c_cloned(c).map(VecDeque::from)
+    }
+}
+
+impl<T: IntoReprC> IntoReprC for VecDeque<T> {
+    fn into_repr_c(self) -> Result<Self::C, Self::Error> {
+        Vec::from(self).into_repr_c()
+    }
+    unsafe fn release_c(c: *mut Self::C) {
+        Vec::<T>::release_c(c)
+    }
+}
+
+impl<T: FromReprCBorrowed> FromReprCBorrowed for VecDeque<T> {
+    type View<'a> = T::SliceView<'a> where T: 'a;
+    type SliceView<'a> = Vec<T::SliceView<'a>> where T: 'a;
+
+    unsafe fn from_repr_c_borrowed<'a>(c: &'a Self::C) -> Result<Self::View<'a>, Self::Error> {
+        T::slice_from_repr_c_borrowed(c)
+    }
+    unsafe fn slice_from_repr_c_borrowed<'a>(c: &'a FfiVec<Self::C>)
+                                             -> Result<Self::SliceView<'a>, Self::Error> {
+        borrowed_each::<Self>(c)
+    }
+}
+
+impl<T: CHeader> CHeader for VecDeque<T> {
+    fn c_decl(name: &str) -> String {
+        Vec::<T>::c_decl(name)
+    }
+    fn c_type_name() -> String {
+        Vec::<T>::c_type_name()
+    }
+    fn declare(header: &mut Header) {
+        Vec::<T>::declare(header)
+    }
+}
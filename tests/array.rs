@@ -0,0 +1,69 @@
+extern crate ffi_trait_poc;
+
+use std::ffi::CString;
+
+use ffi_trait_poc::ipc::IpcError;
+use ffi_trait_poc::{ErrorPath, FromReprC, Header, IntoReprC, OwnedFfi, ReprC};
+
+#[derive(Clone, Debug, PartialEq, ReprC)]
+#[repr_c(error = IpcError)]
+pub struct Keyed {
+    pub key: [u8; 32],
+    pub hash: [u64; 4],
+    pub names: [String; 2],
+    pub grid: [[i32; 3]; 2],
+}
+
+fn keyed() -> Keyed {
+    Keyed {
+        key: [7; 32],
+        hash: [1, 2, 3, 4],
+        names: ["a".to_owned(), "b".to_owned()],
+        grid: [[1, 2, 3], [4, 5, 6]],
+    }
+}
+
+#[test]
+fn embeds_inline_and_converts_back() {
+    let keyed_owned = OwnedFfi::new(keyed()).unwrap();
+    assert_eq!(keyed_owned.key, [7; 32]);
+    assert_eq!(keyed_owned.grid, [[1, 2, 3], [4, 5, 6]]);
+    assert_eq!(keyed_owned.view().unwrap().names, ["a", "b"]);
+    assert_eq!(keyed_owned.to_rust().unwrap(), keyed());
+    assert_eq!(keyed_owned.into_rust().unwrap(), keyed());
+}
+
+#[test]
+fn vec_of_pod_arrays_hands_over_its_buffer() {
+    let keys = vec![[1u8; 32], [2; 32]];
+    let ptr = keys.as_ptr();
+    let keys_owned = OwnedFfi::new(keys).unwrap();
+    assert_eq!(keys_owned.ptr as *const [u8; 32], ptr);
+    let keys = keys_owned.into_rust().unwrap();
+    assert_eq!(keys.as_ptr(), ptr);
+    assert_eq!(keys, [[1; 32], [2; 32]]);
+}
+
+#[test]
+fn errors_carry_the_element_index() {
+    let mut names_ffi = ["a".to_owned(), "b".to_owned()].into_repr_c().unwrap();
+    unsafe { drop(CString::from_raw(names_ffi[1])) };
+    names_ffi[1] = unsafe { CString::from_vec_unchecked(vec![0xff]) }.into_raw();
+    let e = unsafe { <[String; 2]>::from_repr_c_owned(&mut names_ffi) }.unwrap_err();
+    assert_eq!(e.path().to_string(), "[1]");
+
+    let e = ["a".to_owned(), "b\0".to_owned()].into_repr_c().unwrap_err();
+    assert_eq!(e.path().to_string(), "[1]");
+}
+
+#[test]
+fn declares_arrays_inline() {
+    let mut header = Header::new("ARRAY_H");
+    header.register::<Keyed>().register::<Vec<[u8; 32]>>();
+    let header = header.render();
+    assert!(header.contains("    uint8_t key[32];\n"));
+    assert!(header.contains("    uint64_t hash[4];\n"));
+    assert!(header.contains("    char *names[2];\n"));
+    assert!(header.contains("    int32_t grid[2][3];\n"));
+    assert!(header.contains("struct FfiVec_uint8_t_32 {\n    uint8_t (*ptr)[32];\n"));
+}
@@ -0,0 +1,62 @@
+#[macro_use]
+extern crate ffi_trait_poc;
+
+use std::mem::MaybeUninit;
+use std::panic;
+
+use ffi_trait_poc::last_error::{clear_last_error, last_error_code, last_error_message};
+use ffi_trait_poc::{catch_panic, CRepr, FromReprC, IntoReprC, StringError};
+
+/// Panics whenever it is converted from C or released, like a buggy hand-written impl.
+struct Fragile;
+
+impl CRepr for Fragile {
+    type C = u8;
+    type Error = StringError;
+}
+
+impl FromReprC for Fragile {
+    unsafe fn from_repr_c_owned(_c: *mut u8) -> Result<Self, StringError> {
+        panic!("cannot take a Fragile")
+    }
+    unsafe fn from_repr_c_cloned(_c: *const u8) -> Result<Self, StringError> {
+        panic!("cannot copy a Fragile")
+    }
+}
+
+impl IntoReprC for Fragile {
+    fn into_repr_c(self) -> Result<u8, StringError> {
+        Ok(0)
+    }
+    unsafe fn release_c(_c: *mut u8) {
+        panic!("cannot release a Fragile")
+    }
+}
+
+export_ffi_fns!(Fragile, fragile_free, fragile_clone);
+
+#[test]
+fn panics_are_returned() {
+    assert_eq!(catch_panic(|| 1).unwrap(), 1);
+    let e = catch_panic(|| panic!("static message")).unwrap_err();
+    assert_eq!(e.message, "static message");
+    let e = catch_panic(|| panic!("formatted {}", 1)).unwrap_err();
+    assert_eq!(e.to_string(), "panicked: formatted 1");
+    let e = catch_panic(|| panic::panic_any(1)).unwrap_err();
+    assert_eq!(e.message, "panic with a non-string payload");
+}
+
+#[test]
+fn exported_functions_record_panics_instead_of_unwinding() {
+    let mut fragile_ffi = Fragile.into_repr_c().unwrap();
+    let mut out = MaybeUninit::uninit();
+    assert!(!unsafe { fragile_clone(&fragile_ffi, out.as_mut_ptr()) });
+    // `FfiErrorCode_Panic`.
+    assert_eq!(last_error_code(), -1);
+    assert_eq!(last_error_message().unwrap(), "panicked: cannot copy a Fragile");
+
+    clear_last_error();
+    unsafe { fragile_free(&mut fragile_ffi) };
+    assert_eq!(last_error_code(), -1);
+    assert_eq!(last_error_message().unwrap(), "panicked: cannot release a Fragile");
+}
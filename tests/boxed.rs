@@ -0,0 +1,217 @@
+// `Box<T>` crosses to C as a pointer, which lets types refer to themselves.
+
+extern crate ffi_trait_poc;
+
+use std::convert::Infallible;
+use std::ptr;
+
+use ffi_trait_poc::boxed::DEFAULT_MAX_DEPTH;
+use ffi_trait_poc::ipc::One;
+use ffi_trait_poc::{BoxError, ErrorCode, ErrorPath, FieldPath, FromReprC, Header, InvalidTag,
+                    NestingError, NestingErrorKind, OwnedFfi, ReprC, StringError};
+
+#[derive(Debug)]
+enum NodeError {
+    String(StringError),
+    Tag(InvalidTag),
+    Nesting(NestingError),
+}
+
+impl From<StringError> for NodeError {
+    fn from(e: StringError) -> Self {
+        NodeError::String(e)
+    }
+}
+
+impl From<InvalidTag> for NodeError {
+    fn from(e: InvalidTag) -> Self {
+        NodeError::Tag(e)
+    }
+}
+
+impl From<NestingError> for NodeError {
+    fn from(e: NestingError) -> Self {
+        NodeError::Nesting(e)
+    }
+}
+
+impl From<BoxError<NodeError>> for NodeError {
+    fn from(e: BoxError<NodeError>) -> Self {
+        e.into_inner()
+    }
+}
+
+impl From<Infallible> for NodeError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl ErrorPath for NodeError {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            NodeError::String(ref e) => e.path(),
+            NodeError::Tag(ref e) => e.path(),
+            NodeError::Nesting(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            NodeError::String(ref mut e) => e.path_mut(),
+            NodeError::Tag(ref mut e) => e.path_mut(),
+            NodeError::Nesting(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, ReprC)]
+#[repr_c(error = NodeError)]
+struct Node {
+    name: String,
+    children: Vec<Node>,
+    parent_info: Option<Box<Node>>,
+}
+
+#[derive(Clone, Debug, PartialEq, ReprC)]
+#[repr_c(error = NodeError)]
+enum Expr {
+    Num { value: i64 },
+    Add { lhs: Box<Expr>, rhs: Box<Expr> },
+}
+
+// A cycle through two types, neither of which names itself.
+#[derive(Debug, PartialEq, ReprC)]
+#[repr_c(error = NodeError)]
+struct Team {
+    members: Vec<Member>,
+}
+
+#[derive(Debug, PartialEq, ReprC)]
+#[repr_c(error = NodeError)]
+struct Member {
+    name: String,
+    team: Option<Box<Team>>,
+}
+
+/// `len` teams, each the team of the only member of the previous one.
+fn teams(len: usize) -> Team {
+    let mut team = Team { members: Vec::new() };
+    for i in 1..len {
+        let member = Member { name: i.to_string(), team: Some(Box::new(team)) };
+        team = Team { members: vec![member] };
+    }
+    team
+}
+
+fn leaf(name: &str) -> Node {
+    Node {
+        name: name.to_owned(),
+        children: Vec::new(),
+        parent_info: None,
+    }
+}
+
+fn tree() -> Node {
+    Node {
+        name: "root".to_owned(),
+        children: vec![leaf("a"), Node { children: vec![leaf("b0")], ..leaf("b") }],
+        parent_info: Some(Box::new(leaf("parent"))),
+    }
+}
+
+/// `len` nodes, each the `parent_info` of the previous one.
+fn chain(len: usize) -> Node {
+    let mut node = leaf("0");
+    for i in 1..len {
+        node = Node { parent_info: Some(Box::new(node)), ..leaf(&i.to_string()) };
+    }
+    node
+}
+
+#[test]
+fn converts_a_tree() {
+    let tree_owned = OwnedFfi::new(tree()).unwrap();
+    assert!(!tree_owned.parent_info.is_null());
+    let view = tree_owned.view().unwrap();
+    assert_eq!(view.children[1].children[0].name, "b0");
+    assert_eq!(view.parent_info.unwrap().name, "parent");
+    assert_eq!(tree_owned.to_rust().unwrap(), tree());
+    assert_eq!(tree_owned.into_rust().unwrap(), tree());
+
+    let boxed = Box::new(tree());
+    assert_eq!(OwnedFfi::new(boxed.clone()).unwrap().into_rust().unwrap(), boxed);
+}
+
+#[test]
+fn converts_recursive_enums() {
+    let num = |value| Box::new(Expr::Num { value });
+    let expr = Expr::Add { lhs: num(1), rhs: Box::new(Expr::Add { lhs: num(2), rhs: num(3) }) };
+    assert_eq!(OwnedFfi::new(expr.clone()).unwrap().into_rust().unwrap(), expr);
+}
+
+#[test]
+fn rejects_null_boxes() {
+    let e = unsafe { Box::<Node>::from_repr_c_cloned(&ptr::null_mut()) }.unwrap_err();
+    match e {
+        BoxError::Nesting(ref e) => assert!(matches!(e.kind, NestingErrorKind::NullPointer)),
+        _ => panic!("unexpected error {:?}", e),
+    }
+    let e = unsafe { Box::<u32>::from_repr_c_cloned(&ptr::null_mut()) }.unwrap_err();
+    assert_eq!(e.error_code(), 3);
+}
+
+#[test]
+fn boxes_any_repr_c_type() {
+    let name = Box::new("name".to_owned());
+    assert_eq!(OwnedFfi::new(name.clone()).unwrap().into_rust().unwrap(), name);
+    let number_owned = OwnedFfi::new(Box::new(7u32)).unwrap();
+    assert_eq!(*number_owned.view().unwrap(), 7);
+    assert_eq!(*number_owned.into_rust().unwrap(), 7);
+    let one = Box::new(One { a: "a".to_owned() });
+    assert_eq!(OwnedFfi::new(one).unwrap().into_rust().unwrap().a, "a");
+}
+
+#[test]
+fn limits_the_depth() {
+    let deepest = OwnedFfi::new(chain(DEFAULT_MAX_DEPTH + 1)).unwrap();
+    assert!(deepest.to_rust().is_err());
+    assert!(deepest.view().is_err());
+    // Taking ownership is not limited, so that the value is released whole.
+    assert_eq!(deepest.into_rust().unwrap(), chain(DEFAULT_MAX_DEPTH + 1));
+    let e = OwnedFfi::new(chain(DEFAULT_MAX_DEPTH + 1)).unwrap().to_rust().unwrap_err();
+    assert_eq!(e.path().to_string(), ["parent_info"; DEFAULT_MAX_DEPTH].join("."));
+    match e {
+        NodeError::Nesting(ref e) => {
+            assert!(matches!(e.kind, NestingErrorKind::TooDeep(DEFAULT_MAX_DEPTH)));
+            assert_eq!(e.error_code(), 10);
+        }
+        _ => panic!("unexpected error {:?}", e),
+    }
+    assert!(OwnedFfi::new(chain(DEFAULT_MAX_DEPTH)).unwrap().to_rust().is_ok());
+}
+
+#[test]
+fn limits_the_depth_of_cycles_through_other_types() {
+    let deepest = OwnedFfi::new(teams(DEFAULT_MAX_DEPTH + 1)).unwrap();
+    match deepest.to_rust().unwrap_err() {
+        NodeError::Nesting(ref e) => {
+            assert!(matches!(e.kind, NestingErrorKind::TooDeep(DEFAULT_MAX_DEPTH)))
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert!(deepest.view().is_err());
+    assert_eq!(deepest.into_rust().unwrap(), teams(DEFAULT_MAX_DEPTH + 1));
+    assert!(OwnedFfi::new(teams(DEFAULT_MAX_DEPTH / 2)).unwrap().to_rust().is_ok());
+}
+
+#[test]
+fn declares_self_referential_structs() {
+    let mut header = Header::new("NODE_H");
+    header.register::<Node>();
+    let header = header.render();
+    let vec = header.find("struct FfiVec_NodeFfi {\n    NodeFfi *ptr;\n").unwrap();
+    let node = header.find("struct NodeFfi {\n    char *name;\n    FfiVec_NodeFfi children;\n    \
+                            NodeFfi *parent_info;\n};")
+                     .unwrap();
+    assert!(vec < node);
+}
@@ -0,0 +1,56 @@
+// Sets and deques share the `FfiVec` representation of `Vec`.
+
+extern crate ffi_trait_poc;
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::ffi::CString;
+
+use ffi_trait_poc::{ErrorCode, ErrorPath, FfiVec, FromReprC, IntoReprC, OwnedFfi, SetError};
+
+#[test]
+fn btree_set_elements_are_sorted() {
+    let set: BTreeSet<u32> = [3, 1, 2].into();
+    let set_owned = OwnedFfi::new(set.clone()).unwrap();
+    assert_eq!(set_owned.view().unwrap(), [1, 2, 3]);
+    assert_eq!(set_owned.into_rust().unwrap(), set);
+}
+
+#[test]
+fn hash_sets_reject_duplicate_elements() {
+    let set: HashSet<String> = ["a".to_owned(), "b".to_owned()].into();
+    assert_eq!(OwnedFfi::new(set.clone()).unwrap().to_rust().unwrap(), set);
+
+    let set_owned = unsafe { OwnedFfi::<HashSet<u32>>::from_c(FfiVec::from_vec(vec![1, 2, 1])) };
+    let e = set_owned.to_rust().unwrap_err();
+    assert!(matches!(e, SetError::DuplicateKey(_)));
+    assert_eq!(e.error_code(), 9);
+    assert_eq!(e.to_string(), "[2]: duplicate key");
+    assert!(set_owned.into_rust().is_err());
+}
+
+#[test]
+fn element_errors_keep_their_index() {
+    let elts = vec![
+        CString::new("a").unwrap().into_raw(),
+        unsafe { CString::from_vec_unchecked(vec![0xff]) }.into_raw(),
+    ];
+    let mut set_ffi = FfiVec::from_vec(elts);
+    let e = unsafe { BTreeSet::<String>::from_repr_c_owned(&mut set_ffi) }.unwrap_err();
+    assert!(matches!(e, SetError::Element(_)));
+    assert_eq!(e.path().to_string(), "[1]");
+}
+
+#[test]
+fn deques_keep_their_order_and_pod_buffers() {
+    let mut deque: VecDeque<String> = ["b".to_owned(), "c".to_owned()].into();
+    deque.push_front("a".to_owned());
+    assert_eq!(OwnedFfi::new(deque.clone()).unwrap().into_rust().unwrap(), deque);
+
+    let v = vec![1u8, 2, 3];
+    let ptr = v.as_ptr();
+    let mut deque_ffi = VecDeque::from(v).into_repr_c().unwrap();
+    assert_eq!(deque_ffi.ptr as *const u8, ptr);
+    let deque = unsafe { VecDeque::<u8>::from_repr_c_owned(&mut deque_ffi) }.unwrap();
+    let v = Vec::from(deque);
+    assert_eq!(v.as_ptr(), ptr);
+}
@@ -0,0 +1,11 @@
+// Functions exported to C trust their pointer arguments, so Rust must call them in `unsafe`.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::executor::ffi_cancel;
+use ffi_trait_poc::ipc::two_ffi_free;
+
+fn main() {
+    ffi_cancel(0x10 as *const _);
+    two_ffi_free(0x10 as *mut _);
+}
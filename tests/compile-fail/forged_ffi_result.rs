@@ -0,0 +1,12 @@
+// Dropping an `FfiResult` frees its description, so it cannot be pointed anywhere else.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::FfiResult;
+
+fn main() {
+    let _ = FfiResult {
+        error_code: 1,
+        description: 0x10 as *const _,
+    };
+}
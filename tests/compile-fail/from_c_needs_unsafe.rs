@@ -0,0 +1,12 @@
+// An `OwnedFfi` releases what it holds, so it must not be given an arbitrary `TwoFfi`.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::ipc::{Two, TwoFfi};
+use ffi_trait_poc::OwnedFfi;
+
+fn forge(two_ffi: TwoFfi) -> OwnedFfi<Two> {
+    OwnedFfi::from_c(two_ffi)
+}
+
+fn main() {}
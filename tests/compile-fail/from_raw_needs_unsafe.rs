@@ -0,0 +1,10 @@
+// Only pointers from `into_raw` may be taken back, which the compiler cannot check.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::ipc::{One, OneFfi};
+use ffi_trait_poc::OwnedFfi;
+
+fn main() {
+    let _ = OwnedFfi::<One>::from_raw(0x10 as *mut OneFfi);
+}
@@ -0,0 +1,11 @@
+// Converting from a raw pointer trusts it to point to a valid `TwoFfi`.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::ipc::{Two, TwoFfi};
+use ffi_trait_poc::FromReprC;
+
+fn main() {
+    let dangling = 0x10 as *mut TwoFfi;
+    let _ = Two::from_repr_c_owned(dangling);
+}
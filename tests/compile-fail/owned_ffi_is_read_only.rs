@@ -0,0 +1,16 @@
+// Pointers inside an `OwnedFfi` cannot be replaced, or dropping it would release them.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::ipc::{One, Two};
+use ffi_trait_poc::OwnedFfi;
+
+fn main() {
+    let two_owned = OwnedFfi::new(Two {
+        a: "a".to_owned(),
+        b: Vec::new(),
+        c: Vec::new(),
+        d: One { a: "d".to_owned() },
+    }).unwrap();
+    two_owned.a = 0x10 as *mut _;
+}
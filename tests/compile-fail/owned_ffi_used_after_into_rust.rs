@@ -0,0 +1,12 @@
+// Converting back consumes the `OwnedFfi`, so its representation cannot be used again.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::ipc::One;
+use ffi_trait_poc::OwnedFfi;
+
+fn main() {
+    let one_owned = OwnedFfi::new(One { a: "a".to_owned() }).unwrap();
+    let _ = one_owned.into_rust();
+    let _ = one_owned.to_rust();
+}
@@ -0,0 +1,13 @@
+// A view borrows from the `OwnedFfi` and cannot outlive it.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::ipc::One;
+use ffi_trait_poc::OwnedFfi;
+
+fn main() {
+    let one_owned = OwnedFfi::new(One { a: "a".to_owned() }).unwrap();
+    let view = one_owned.view().unwrap();
+    drop(one_owned);
+    println!("{}", view.a);
+}
@@ -0,0 +1,9 @@
+// Safe code must not be able to hand the crate C representations it did not make, nor reach
+// what an `OwnedFfi` owns; each case in `compile-fail` tries one way and must be rejected.
+
+extern crate trybuild;
+
+#[test]
+fn safe_code_cannot_misuse_c_representations() {
+    trybuild::TestCases::new().compile_fail("tests/compile-fail/*.rs");
+}
@@ -0,0 +1,93 @@
+// `set_max_depth` applies to every thread, so changing it gets a test binary of its own rather than
+// racing the other tests converting recursive types.
+
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::boxed::{self, DEFAULT_MAX_DEPTH};
+use ffi_trait_poc::{BoxError, ErrorPath, FieldPath, NestingError, NestingErrorKind, OwnedFfi,
+                    ReprC, StringError};
+
+#[derive(Debug)]
+enum LinkError {
+    String(StringError),
+    Nesting(NestingError),
+}
+
+impl From<StringError> for LinkError {
+    fn from(e: StringError) -> Self {
+        LinkError::String(e)
+    }
+}
+
+impl From<NestingError> for LinkError {
+    fn from(e: NestingError) -> Self {
+        LinkError::Nesting(e)
+    }
+}
+
+impl From<BoxError<LinkError>> for LinkError {
+    fn from(e: BoxError<LinkError>) -> Self {
+        e.into_inner()
+    }
+}
+
+impl ErrorPath for LinkError {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            LinkError::String(ref e) => e.path(),
+            LinkError::Nesting(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            LinkError::String(ref mut e) => e.path_mut(),
+            LinkError::Nesting(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, ReprC)]
+#[repr_c(error = LinkError)]
+struct Link {
+    name: String,
+    next: Option<Box<Link>>,
+}
+
+fn chain(len: usize) -> Link {
+    let mut link = None;
+    for i in 0..len {
+        link = Some(Box::new(Link { name: i.to_string(), next: link }));
+    }
+    *link.unwrap()
+}
+
+/// Sets the maximum depth, restoring the default when dropped, even if the test fails.
+struct MaxDepth;
+
+impl MaxDepth {
+    fn set(depth: usize) -> Self {
+        boxed::set_max_depth(depth);
+        MaxDepth
+    }
+}
+
+impl Drop for MaxDepth {
+    fn drop(&mut self) {
+        boxed::set_max_depth(DEFAULT_MAX_DEPTH);
+    }
+}
+
+#[test]
+fn max_depth_can_be_lowered() {
+    let _max_depth = MaxDepth::set(8);
+    let too_deep = OwnedFfi::new(chain(9)).unwrap();
+    let e = too_deep.to_rust().unwrap_err();
+    assert_eq!(e.path().to_string(), ["next"; 8].join("."));
+    match e {
+        LinkError::Nesting(ref e) => assert!(matches!(e.kind, NestingErrorKind::TooDeep(8))),
+        _ => panic!("unexpected error {:?}", e),
+    }
+    assert!(too_deep.view().is_err());
+    assert!(OwnedFfi::new(chain(8)).unwrap().to_rust().is_ok());
+    assert_eq!(too_deep.into_rust().unwrap(), chain(9));
+}
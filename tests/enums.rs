@@ -0,0 +1,129 @@
+extern crate ffi_trait_poc;
+
+use std::convert::Infallible;
+use std::mem;
+
+use ffi_trait_poc::{ErrorCode, ErrorPath, FieldPath, Header, InvalidTag, IntoReprC, OwnedFfi,
+                    ReprC, StringError};
+
+#[derive(Clone, Copy, Debug, PartialEq, ReprC)]
+enum Color {
+    Red,
+    Green = 5,
+    Blue,
+}
+
+#[derive(Debug)]
+enum ShapeError {
+    String(StringError),
+    Tag(InvalidTag),
+}
+
+impl From<StringError> for ShapeError {
+    fn from(e: StringError) -> Self {
+        ShapeError::String(e)
+    }
+}
+
+impl From<InvalidTag> for ShapeError {
+    fn from(e: InvalidTag) -> Self {
+        ShapeError::Tag(e)
+    }
+}
+
+impl From<Infallible> for ShapeError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl ErrorPath for ShapeError {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            ShapeError::String(ref e) => e.path(),
+            ShapeError::Tag(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            ShapeError::String(ref mut e) => e.path_mut(),
+            ShapeError::Tag(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, ReprC)]
+#[repr_c(error = ShapeError)]
+enum Shape {
+    Empty,
+    Circle { radius: f64, color: Color },
+    Label(String),
+}
+
+#[test]
+fn c_like_enums_number_their_variants_like_rust() {
+    assert_eq!(Color::Red.into_repr_c().unwrap(), 0);
+    assert_eq!(Color::Green.into_repr_c().unwrap(), 5);
+    assert_eq!(Color::Blue.into_repr_c().unwrap(), 6);
+    assert_eq!(unsafe { OwnedFfi::<Color>::from_c(6) }.into_rust().unwrap(), Color::Blue);
+
+    let mut header = Header::new("COLOR_H");
+    header.register::<Color>();
+    let header = header.render();
+    assert!(header.contains("typedef uint32_t ColorFfi;\n"));
+    assert!(header.contains("    ColorFfi_Green = 5,\n    ColorFfi_Blue = 6,\n"));
+}
+
+#[test]
+fn c_like_enums_reject_unknown_tags() {
+    for tag in [1, 7] {
+        let e = unsafe { OwnedFfi::<Color>::from_c(tag) }.into_rust().unwrap_err();
+        assert_eq!((e.type_name, e.tag), ("Color", tag));
+        assert_eq!(e.error_code(), 6);
+    }
+    let colors = OwnedFfi::new(vec![Color::Red, Color::Blue]).unwrap();
+    assert_eq!(colors.view().unwrap(), [Color::Red, Color::Blue]);
+}
+
+#[test]
+fn unit_variants_carry_only_their_tag() {
+    let empty_owned = OwnedFfi::new(Shape::Empty).unwrap();
+    assert_eq!(empty_owned.tag, 0);
+    assert!(matches!(empty_owned.view().unwrap(), ShapeView::Empty));
+    assert_eq!(empty_owned.into_rust().unwrap(), Shape::Empty);
+
+    let shapes = vec![Shape::Label("a".to_owned()),
+                      Shape::Empty,
+                      Shape::Circle { radius: 1.5, color: Color::Blue }];
+    let shapes_owned = OwnedFfi::new(shapes.clone()).unwrap();
+    let tags = unsafe { shapes_owned.as_slice() }.iter().map(|s| s.tag).collect::<Vec<_>>();
+    assert_eq!(tags, [2, 0, 1]);
+    assert_eq!(shapes_owned.into_rust().unwrap(), shapes);
+}
+
+#[test]
+fn tagged_unions_reject_unknown_tags() {
+    let shape_ffi = ShapeFfi {
+        tag: 3,
+        payload: unsafe { mem::zeroed() },
+    };
+    let shape_owned = unsafe { OwnedFfi::<Shape>::from_c(shape_ffi) };
+    match shape_owned.view().unwrap_err() {
+        ShapeError::Tag(e) => assert_eq!(e.to_string(), "invalid tag 3 for Shape"),
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert!(shape_owned.into_rust().is_err());
+}
+
+#[test]
+fn paths_name_the_variant() {
+    let e = Shape::Label("a\0".to_owned()).into_repr_c().unwrap_err();
+    assert_eq!(e.path().to_string(), "label.0");
+
+    let mut circle_ffi = Shape::Circle { radius: 1.0, color: Color::Red }.into_repr_c().unwrap();
+    unsafe { (*circle_ffi.payload.circle).color = 4 };
+    let circle_owned = unsafe { OwnedFfi::<Shape>::from_c(circle_ffi) };
+    assert_eq!(circle_owned.to_rust().unwrap_err().path().to_string(), "circle.color");
+    assert_eq!(circle_owned.view().unwrap_err().path().to_string(), "circle.color");
+    assert_eq!(circle_owned.into_rust().unwrap_err().path().to_string(), "circle.color");
+}
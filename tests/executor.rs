@@ -0,0 +1,80 @@
+extern crate ffi_trait_poc;
+
+use std::future;
+use std::os::raw::c_void;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::task::Poll;
+use std::time::Duration;
+
+use ffi_trait_poc::executor::{ffi_cancel, ffi_cancel_handle_free};
+use ffi_trait_poc::{spawn, Callback, Cancelled, FfiResult};
+
+/// Error code and value a callback was called with.
+type Call = (i32, Option<u32>);
+
+extern "C" fn record(user_data: *mut c_void, result: *const FfiResult, value: *const u32) {
+    let calls = unsafe { &*(user_data as *const Sender<Call>) };
+    let value = if value.is_null() { None } else { Some(unsafe { *value }) };
+    calls.send((unsafe { (*result).error_code() }, value)).unwrap();
+}
+
+/// Records the calls of the callbacks it hands out.
+struct Calls {
+    sender: Box<Sender<Call>>,
+    receiver: Receiver<Call>,
+}
+
+impl Calls {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Calls {
+            sender: Box::new(sender),
+            receiver,
+        }
+    }
+
+    fn callback(&self) -> Callback<u32> {
+        // `record` only sends through the `Sender`, which outlives the callbacks of every test.
+        unsafe { Callback::new(&*self.sender as *const Sender<Call> as *mut c_void, record) }
+    }
+
+    fn next(&self) -> Call {
+        self.receiver.recv_timeout(Duration::from_secs(10)).expect("no callback")
+    }
+
+    fn assert_no_more(&self) {
+        assert!(self.receiver.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
+
+#[test]
+fn calls_back_once_with_the_output() {
+    let calls = Calls::new();
+    let handle = spawn(future::ready(Ok::<u32, Cancelled>(7)), calls.callback());
+    assert_eq!(calls.next(), (0, Some(7)));
+    handle.cancel();
+    calls.assert_no_more();
+}
+
+#[test]
+fn calls_back_once_when_cancelled() {
+    let calls = Calls::new();
+    let handle = spawn(future::pending::<Result<u32, Cancelled>>(), calls.callback()).into_raw();
+    unsafe { ffi_cancel(handle) };
+    // `FfiErrorCode_Cancelled`.
+    assert_eq!(calls.next(), (7, None));
+    unsafe { ffi_cancel(handle) };
+    calls.assert_no_more();
+    unsafe { ffi_cancel_handle_free(handle) };
+}
+
+#[test]
+fn calls_back_once_when_the_future_panics() {
+    let calls = Calls::new();
+    let panicking = future::poll_fn(|_| -> Poll<Result<u32, Cancelled>> { panic!("boom") });
+    let handle = spawn(panicking, calls.callback());
+    // `FfiErrorCode_Panic`.
+    assert_eq!(calls.next(), (-1, None));
+    handle.cancel();
+    calls.assert_no_more();
+}
@@ -0,0 +1,48 @@
+#[macro_use]
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::handle::{FfiHandle, Registry};
+use ffi_trait_poc::last_error::{clear_last_error, last_error_code, last_error_message};
+
+static SESSIONS: Registry<String> = Registry::new();
+
+export_handle_free!(SESSIONS, session_free);
+
+#[test]
+fn stale_handles_do_not_reach_the_reused_slot() {
+    let registry = Registry::new();
+    let first = registry.insert("first");
+    assert_eq!(*registry.remove(first).unwrap(), "first");
+
+    let second = registry.insert("second");
+    assert_eq!(second.0 & 0xffff_ffff, first.0 & 0xffff_ffff);
+    assert_ne!(second, first);
+    assert_eq!(registry.get(first).unwrap_err().handle, first);
+    assert!(registry.remove(first).is_err());
+    assert_eq!(*registry.get(second).unwrap(), "second");
+}
+
+#[test]
+fn zero_is_never_a_handle() {
+    let registry = Registry::new();
+    assert!(registry.get(FfiHandle(0)).is_err());
+    let handles = (0..3).map(|i| registry.insert(i)).collect::<Vec<_>>();
+    assert!(!handles.contains(&FfiHandle(0)));
+    assert!(registry.get(FfiHandle(0)).is_err());
+    assert!(registry.remove(FfiHandle(0)).is_err());
+    assert_eq!(*registry.get(handles[0]).unwrap(), 0);
+}
+
+#[test]
+fn exported_free_rejects_a_second_free() {
+    let handle = SESSIONS.insert("session".to_owned());
+    clear_last_error();
+    assert!(session_free(handle));
+    assert_eq!(last_error_code(), 0);
+
+    assert!(!session_free(handle));
+    // `FfiErrorCode_InvalidHandle`.
+    assert_eq!(last_error_code(), 8);
+    assert_eq!(last_error_message().unwrap(), format!("invalid handle {:#x}", handle.0));
+    assert!(!session_free(FfiHandle(0)));
+}
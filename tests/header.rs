@@ -0,0 +1,66 @@
+extern crate ffi_trait_poc;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::process::{self, Command};
+
+use ffi_trait_poc::ipc::One;
+use ffi_trait_poc::Header;
+
+#[test]
+fn generated_header_compiles() {
+    let dir = env::temp_dir().join(format!("ffi-trait-poc-header-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let header = dir.join("ffi_trait_poc.h");
+    let source = dir.join("main.c");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_ffi-trait-poc"))
+        .arg("--header")
+        .arg(&header)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    File::create(&source)
+        .unwrap()
+        .write_all(b"#include \"ffi_trait_poc.h\"\n\
+                     size_t two_ffi_size(void) { return sizeof(TwoFfi); }\n\
+                     void release(TwoFfi *two) { two_ffi_free(two); }\n\
+                     bool is_utf8_error(const FfiResult *res) {\n\
+                         return res->error_code == FfiErrorCode_InvalidUtf8;\n\
+                     }\n\
+                     void o_cb(void *user_data, const FfiResult *result, const TwoFfi *value) {\n\
+                         (void)user_data; (void)result; (void)value;\n\
+                     }\n\
+                     TwoFfiCallback callback = o_cb;\n\
+                     void cancel(FfiCancelHandle *handle) {\n\
+                         ffi_cancel(handle);\n\
+                         ffi_cancel_handle_free(handle);\n\
+                     }\n")
+        .unwrap();
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(cc)
+        .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror", "-c", "-o"])
+        .arg(dir.join("main.o"))
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn declares_prototypes_of_exported_functions_only() {
+    let mut header = Header::new("ONE_H");
+    header.register::<One>();
+    assert!(!header.render().contains("one_ffi_free"));
+
+    header.register_ffi_fns::<One>("one_ffi_free", "one_ffi_clone");
+    let header = header.render();
+    assert!(header.contains("\nvoid one_ffi_free(OneFfi *value);\n"));
+    assert!(header.contains("\nbool one_ffi_clone(const OneFfi *value, OneFfi *out);\n"));
+    assert_eq!(header.matches("struct OneFfi {").count(), 1);
+}
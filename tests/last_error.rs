@@ -0,0 +1,81 @@
+extern crate ffi_trait_poc;
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::os::raw::c_char;
+use std::thread;
+
+use ffi_trait_poc::ipc::{self, One, Two, TwoFfi};
+use ffi_trait_poc::last_error::{ffi_clear_last_error, ffi_last_error_code, ffi_last_error_message};
+use ffi_trait_poc::IntoReprC;
+
+/// A `TwoFfi` whose `d.a` is not valid UTF-8.
+fn invalid_two_ffi() -> TwoFfi {
+    let two = Two {
+        a: "a".to_owned(),
+        b: vec![1, 2, 3],
+        c: Vec::new(),
+        d: One { a: "d".to_owned() },
+    };
+    let mut two_ffi = two.into_repr_c().unwrap();
+    unsafe { drop(CString::from_raw(two_ffi.d.a)) };
+    two_ffi.d.a = unsafe { CString::from_vec_unchecked(vec![0xff]) }.into_raw();
+    two_ffi
+}
+
+/// Fails a clone on the calling thread, recording its error.
+fn fail_clone() {
+    let mut two_ffi = invalid_two_ffi();
+    let mut out = MaybeUninit::<TwoFfi>::uninit();
+    assert!(!unsafe { ipc::two_ffi_clone(&two_ffi, out.as_mut_ptr()) });
+    unsafe { ipc::two_ffi_free(&mut two_ffi) };
+}
+
+fn message() -> String {
+    let len = unsafe { ffi_last_error_message(std::ptr::null_mut(), 0) };
+    let mut buf = vec![0 as c_char; len + 1];
+    assert_eq!(unsafe { ffi_last_error_message(buf.as_mut_ptr(), buf.len()) }, len);
+    let bytes = buf[..len].iter().map(|&c| c as u8).collect();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn failures_are_recorded() {
+    fail_clone();
+    // `FfiErrorCode_InvalidUtf8`.
+    assert_eq!(ffi_last_error_code(), 1);
+    assert!(message().starts_with("d.a: "), "{}", message());
+
+    ffi_clear_last_error();
+    assert_eq!(ffi_last_error_code(), 0);
+    assert_eq!(message(), "");
+}
+
+#[test]
+fn message_is_truncated_to_the_buffer() {
+    fail_clone();
+    let len = message().len();
+    let mut buf = [1 as c_char; 4];
+    assert_eq!(unsafe { ffi_last_error_message(buf.as_mut_ptr(), buf.len()) }, len);
+    assert_eq!(buf, [b'd' as c_char, b'.' as c_char, b'a' as c_char, 0]);
+}
+
+#[test]
+fn threads_have_their_own_last_error() {
+    fail_clone();
+
+    thread::spawn(|| {
+        assert_eq!(ffi_last_error_code(), 0);
+        assert_eq!(message(), "");
+        fail_clone();
+        ffi_clear_last_error();
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(ffi_last_error_code(), 1);
+
+    ffi_clear_last_error();
+    thread::spawn(fail_clone).join().unwrap();
+    assert_eq!(ffi_last_error_code(), 0);
+}
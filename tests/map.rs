@@ -0,0 +1,72 @@
+extern crate ffi_trait_poc;
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+use ffi_trait_poc::{ErrorCode, ErrorPath, FfiEntry, FfiVec, FromReprC, Header, MapError,
+                    OwnedFfi};
+
+fn entry(key: u32, value: &str) -> FfiEntry<u32, *mut c_char> {
+    FfiEntry {
+        key,
+        value: CString::new(value).unwrap().into_raw(),
+    }
+}
+
+#[test]
+fn btree_map_entries_are_sorted_by_key() {
+    let map: BTreeMap<u32, String> = [(3, "c"), (1, "a"), (2, "b")]
+        .iter()
+        .map(|&(k, v)| (k, v.to_owned()))
+        .collect();
+    let map_owned = OwnedFfi::new(map.clone()).unwrap();
+    let entries = map_owned.view().unwrap();
+    assert_eq!(entries, [(1, "a"), (2, "b"), (3, "c")]);
+    let values = unsafe { map_owned.as_slice() }
+        .iter()
+        .map(|e| unsafe { CStr::from_ptr(e.value) }.to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, ["a", "b", "c"]);
+    assert_eq!(map_owned.into_rust().unwrap(), map);
+}
+
+#[test]
+fn hash_maps_reject_duplicate_keys() {
+    let map: HashMap<String, Vec<u8>> = [("a".to_owned(), vec![1]), ("b".to_owned(), vec![])]
+        .into();
+    assert_eq!(OwnedFfi::new(map.clone()).unwrap().to_rust().unwrap(), map);
+
+    let map_ffi = FfiVec::from_vec(vec![entry(1, "a"), entry(2, "b"), entry(1, "c")]);
+    let map_owned = unsafe { OwnedFfi::<HashMap<u32, String>>::from_c(map_ffi) };
+    let e = map_owned.to_rust().unwrap_err();
+    assert!(matches!(e, MapError::DuplicateKey(_)));
+    assert_eq!(e.error_code(), 9);
+    assert_eq!(e.path().to_string(), "[2].key");
+    assert_eq!(e.to_string(), "[2].key: duplicate key");
+    assert!(map_owned.into_rust().is_err());
+}
+
+#[test]
+fn errors_name_the_entry_and_its_half() {
+    let invalid_utf8 = FfiEntry {
+        key: 2,
+        value: unsafe { CString::from_vec_unchecked(vec![0xff]) }.into_raw(),
+    };
+    let mut map_ffi = FfiVec::from_vec(vec![entry(1, "a"), invalid_utf8]);
+    let e = unsafe { BTreeMap::<u32, String>::from_repr_c_owned(&mut map_ffi) }.unwrap_err();
+    assert!(matches!(e, MapError::Value(_)));
+    assert_eq!(e.path().to_string(), "[1].value");
+}
+
+#[test]
+fn entries_are_declared_before_the_map() {
+    let mut header = Header::new("MAP_H");
+    header.register::<BTreeMap<u32, String>>();
+    let header = header.render();
+    let entry = "struct FfiEntry_uint32_t_String {\n    uint32_t key;\n    char *value;\n";
+    let entry = header.find(entry).unwrap();
+    let map = header.find("struct FfiMap_uint32_t_String {\n    FfiEntry_uint32_t_String *ptr;\n")
+                    .unwrap();
+    assert!(entry < map);
+}
@@ -0,0 +1,93 @@
+extern crate ffi_trait_poc;
+
+use std::ptr;
+
+use ffi_trait_poc::ipc::IpcError;
+use ffi_trait_poc::{FfiNullable, Header, OwnedFfi, ReprC};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ReprC)]
+#[repr_c(error = IpcError)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, ReprC)]
+#[repr_c(error = IpcError)]
+struct Profile {
+    nickname: Option<String>,
+    avatar: Option<Vec<u8>>,
+    age: Option<u32>,
+    home: Option<Point>,
+}
+
+fn profile() -> Profile {
+    Profile {
+        nickname: Some("nick".to_owned()),
+        avatar: Some(Vec::new()),
+        age: Some(42),
+        home: Some(Point { x: 1, y: -1 }),
+    }
+}
+
+#[test]
+fn none_is_null_or_not_is_some() {
+    let none_owned = OwnedFfi::new(Profile::default()).unwrap();
+    assert!(none_owned.nickname.is_null());
+    assert!(none_owned.avatar.ptr.is_null());
+    assert!(!none_owned.age.is_some);
+    assert!(!none_owned.home.is_some);
+    assert_eq!(none_owned.view().unwrap().nickname, None);
+    assert_eq!(none_owned.into_rust().unwrap(), Profile::default());
+
+    let null = unsafe { OwnedFfi::<Option<String>>::from_c(FfiNullable(ptr::null_mut())) };
+    assert_eq!(null.into_rust().unwrap(), None);
+    let null = unsafe { OwnedFfi::<String>::from_c(ptr::null_mut()) };
+    assert!(null.to_rust().is_err());
+}
+
+#[test]
+fn some_keeps_its_value() {
+    let some_owned = OwnedFfi::new(profile()).unwrap();
+    assert!(!some_owned.nickname.is_null());
+    // Empty vectors still have a non-null buffer, which tells them from `None`.
+    assert!(!some_owned.avatar.ptr.is_null());
+    assert!(some_owned.age.is_some);
+    assert_eq!(unsafe { some_owned.age.value.assume_init() }, 42);
+
+    let view = some_owned.view().unwrap();
+    assert_eq!(view.nickname, Some("nick"));
+    assert_eq!(view.avatar, Some(&[][..]));
+    assert_eq!(view.home.unwrap().y, -1);
+    assert_eq!(some_owned.to_rust().unwrap(), profile());
+    assert_eq!(some_owned.into_rust().unwrap(), profile());
+}
+
+#[test]
+fn nested_options_keep_some_none_apart_from_none() {
+    for nested in [None, Some(None), Some(Some("a".to_owned()))] {
+        let nested_owned = OwnedFfi::new(nested.clone()).unwrap();
+        assert_eq!(nested_owned.is_some, nested.is_some());
+        assert_eq!(nested_owned.view().unwrap(), nested.as_ref().map(|n| n.as_deref()));
+        assert_eq!(nested_owned.into_rust().unwrap(), nested);
+    }
+    for nested in [None, Some(None), Some(Some(vec![1u8]))] {
+        assert_eq!(OwnedFfi::new(nested.clone()).unwrap().into_rust().unwrap(), nested);
+    }
+
+    let mut header = Header::new("NESTED_H");
+    header.register::<Option<Option<String>>>();
+    let option = "struct FfiOption_String {\n    bool is_some;\n    char *value;\n};";
+    assert!(header.render().contains(option));
+}
+
+#[test]
+fn declares_ffi_option_for_values_without_null() {
+    let mut header = Header::new("PROFILE_H");
+    header.register::<Profile>();
+    let header = header.render();
+    assert!(header.contains("    char *nickname;\n    FfiBytes avatar;\n"));
+    assert!(header.contains("    FfiOption_uint32_t age;\n    FfiOption_PointFfi home;\n"));
+    let option = "struct FfiOption_uint32_t {\n    bool is_some;\n    uint32_t value;\n};";
+    assert!(header.contains(option));
+}
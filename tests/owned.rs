@@ -0,0 +1,69 @@
+// `OwnedFfi` is the safe way to hold a C representation on the Rust side; these exercise it through
+// the same `Two` the exported functions are generated for.
+
+extern crate ffi_trait_poc;
+
+use std::mem::MaybeUninit;
+
+use ffi_trait_poc::ipc::{self, One, Two, TwoFfi};
+use ffi_trait_poc::{OwnedFfi, IntoReprC};
+
+fn two() -> Two {
+    Two {
+        a: "a".to_owned(),
+        b: vec![1, 2, 3],
+        c: vec![One { a: "c".to_owned() }],
+        d: One { a: "d".to_owned() },
+    }
+}
+
+fn assert_two(two: &Two) {
+    assert_eq!(two.a, "a");
+    assert_eq!(two.b, [1, 2, 3]);
+    assert_eq!(two.c.len(), 1);
+    assert_eq!(two.c[0].a, "c");
+    assert_eq!(two.d.a, "d");
+}
+
+#[test]
+fn converts_back_and_forth() {
+    let two_owned = OwnedFfi::new(two()).unwrap();
+    assert_two(&two_owned.to_rust().unwrap());
+    assert_two(&two_owned.into_rust().unwrap());
+}
+
+#[test]
+fn views_without_converting() {
+    let two_owned = OwnedFfi::new(two()).unwrap();
+    let view = two_owned.view().unwrap();
+    assert_eq!(view.a, "a");
+    assert_eq!(view.b, [1, 2, 3]);
+    assert_eq!(view.c[0].a, "c");
+    assert_eq!(view.d.a, "d");
+}
+
+#[test]
+fn hands_over_to_c_and_back() {
+    let two_owned = OwnedFfi::new(two()).unwrap();
+    let mut copy = MaybeUninit::<TwoFfi>::uninit();
+    assert!(unsafe { ipc::two_ffi_clone(two_owned.as_ptr(), copy.as_mut_ptr()) });
+    let copy = unsafe { OwnedFfi::<Two>::from_c(copy.assume_init()) };
+    assert_two(&copy.into_rust().unwrap());
+
+    let two_ffi = two_owned.into_c();
+    assert_two(&unsafe { OwnedFfi::<Two>::from_c(two_ffi) }.into_rust().unwrap());
+}
+
+#[test]
+fn from_c_takes_what_into_repr_c_made() {
+    let two_ffi = two().into_repr_c().unwrap();
+    let two_owned = unsafe { OwnedFfi::<Two>::from_c(two_ffi) };
+    assert_two(&two_owned.to_rust().unwrap());
+}
+
+#[test]
+fn raw_pointers_come_back_through_from_raw() {
+    let ptr = OwnedFfi::new(two()).unwrap().into_raw();
+    let two_owned = unsafe { OwnedFfi::<Two>::from_raw(ptr) };
+    assert_two(&two_owned.into_rust().unwrap());
+}
@@ -0,0 +1,52 @@
+extern crate ffi_trait_poc;
+
+use ffi_trait_poc::{ErrorCode, ErrorPath, FfiVec, OwnedFfi, PrimitiveError, PrimitiveErrorKind,
+                    ReprC};
+
+#[derive(Clone, Debug, PartialEq, ReprC)]
+#[repr_c(error = PrimitiveError)]
+struct Key {
+    pressed: bool,
+    glyph: char,
+}
+
+#[test]
+fn bool_is_0_or_1() {
+    let key_owned = OwnedFfi::new(Key { pressed: true, glyph: 'é' }).unwrap();
+    assert_eq!(key_owned.pressed, 1);
+    assert_eq!(key_owned.glyph, 0xe9);
+    assert_eq!(key_owned.into_rust().unwrap(), Key { pressed: true, glyph: 'é' });
+
+    for v in [2, 0xff] {
+        let e = unsafe { OwnedFfi::<bool>::from_c(v) }.into_rust().unwrap_err();
+        assert!(matches!(e.kind, PrimitiveErrorKind::InvalidBool(b) if b == v));
+        assert_eq!(e.error_code(), 4);
+    }
+    let flags = unsafe { OwnedFfi::<Vec<bool>>::from_c(FfiVec::from_vec(vec![0, 1, 2])) };
+    assert_eq!(flags.view().unwrap_err().to_string(), "[2]: invalid bool 2, expected 0 or 1");
+}
+
+#[test]
+fn char_is_a_unicode_scalar_value() {
+    for v in [0xd800, 0xdfff, 0x11_0000] {
+        let e = unsafe { OwnedFfi::<char>::from_c(v) }.to_rust().unwrap_err();
+        assert!(matches!(e.kind, PrimitiveErrorKind::InvalidChar(c) if c == v));
+        assert_eq!(e.error_code(), 5);
+    }
+    let key_ffi = KeyFfi { pressed: 0, glyph: 0xd800 };
+    let e = unsafe { OwnedFfi::<Key>::from_c(key_ffi) }.to_rust().unwrap_err();
+    assert_eq!(e.path().to_string(), "glyph");
+    assert_eq!(e.to_string(), "glyph: invalid char 0xd800");
+}
+
+#[test]
+fn vec_of_integers_hands_over_its_buffer() {
+    let v = vec![1u32, 0, u32::MAX];
+    let ptr = v.as_ptr();
+    let v_owned = OwnedFfi::new(v).unwrap();
+    assert_eq!(v_owned.ptr as *const u32, ptr);
+    assert_eq!(v_owned.view().unwrap(), [1, 0, u32::MAX]);
+    let v = v_owned.into_rust().unwrap();
+    assert_eq!(v.as_ptr(), ptr);
+    assert_eq!(v, [1, 0, u32::MAX]);
+}
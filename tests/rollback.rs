@@ -0,0 +1,272 @@
+// Conversions failing part way through must release everything they already built or were handed,
+// and `OwnedFfi` must release what it owns exactly once. A counting allocator checks that no
+// allocation outlives either, and that none is released twice.
+
+extern crate ffi_trait_poc;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use ffi_trait_poc::boxed::DEFAULT_MAX_DEPTH;
+use ffi_trait_poc::ipc::{One, Two};
+use ffi_trait_poc::{BoxError, CRepr, ErrorPath, FfiVec, FieldPath, FromReprC, IntoReprC,
+                    InvalidTag, NestingError, OwnedFfi, ReprC, StringError};
+
+struct Counting;
+
+thread_local! {
+    // Per thread, as the tests run in parallel.
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+fn adjust(by: isize) {
+    let _ = LIVE.try_with(|live| live.set(live.get() + by));
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        adjust(1);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        adjust(-1);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Asserts that `f` leaves as many allocations live as there were before it.
+fn assert_no_leak<F: FnOnce()>(f: F) {
+    let before = LIVE.with(Cell::get);
+    f();
+    assert_eq!(LIVE.with(Cell::get), before);
+}
+
+fn one(a: &str) -> One {
+    One { a: a.to_owned() }
+}
+
+fn two(d: &str) -> Two {
+    Two {
+        a: "a".to_owned(),
+        b: vec![1, 2, 3],
+        c: vec![one("c0"), one("c1")],
+        d: one(d),
+    }
+}
+
+/// A C string that is not valid UTF-8.
+fn invalid_utf8() -> *mut c_char {
+    unsafe { CString::from_vec_unchecked(vec![0xff]) }.into_raw()
+}
+
+#[derive(Debug)]
+enum Error {
+    String(StringError),
+    Tag(InvalidTag),
+    Nesting(NestingError),
+}
+
+impl From<StringError> for Error {
+    fn from(e: StringError) -> Self {
+        Error::String(e)
+    }
+}
+
+impl From<InvalidTag> for Error {
+    fn from(e: InvalidTag) -> Self {
+        Error::Tag(e)
+    }
+}
+
+impl From<NestingError> for Error {
+    fn from(e: NestingError) -> Self {
+        Error::Nesting(e)
+    }
+}
+
+impl From<BoxError<Error>> for Error {
+    fn from(e: BoxError<Error>) -> Self {
+        e.into_inner()
+    }
+}
+
+impl From<Infallible> for Error {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl ErrorPath for Error {
+    fn path(&self) -> &FieldPath {
+        match *self {
+            Error::String(ref e) => e.path(),
+            Error::Tag(ref e) => e.path(),
+            Error::Nesting(ref e) => e.path(),
+        }
+    }
+    fn path_mut(&mut self) -> &mut FieldPath {
+        match *self {
+            Error::String(ref mut e) => e.path_mut(),
+            Error::Tag(ref mut e) => e.path_mut(),
+            Error::Nesting(ref mut e) => e.path_mut(),
+        }
+    }
+}
+
+/// Only ever handed to C, so it has no `FromReprC` to release it through.
+struct Report(String);
+
+impl CRepr for Report {
+    type C = *mut c_char;
+    type Error = StringError;
+}
+
+impl IntoReprC for Report {
+    fn into_repr_c(self) -> Result<*mut c_char, StringError> {
+        self.0.into_repr_c()
+    }
+    unsafe fn release_c(c: *mut *mut c_char) {
+        String::release_c(c)
+    }
+}
+
+#[derive(ReprC)]
+#[repr_c(error = Error)]
+enum Message {
+    Text { text: String, bytes: Vec<u8>, words: Vec<String> },
+}
+
+#[derive(Debug, PartialEq, ReprC)]
+#[repr_c(error = Error)]
+struct Link {
+    name: String,
+    next: Option<Box<Link>>,
+}
+
+/// `len` links, nested deeper than the depth limit for large enough `len`.
+fn chain(len: usize) -> Link {
+    let mut link = None;
+    for i in 0..len {
+        link = Some(Box::new(Link { name: i.to_string(), next: link }));
+    }
+    *link.unwrap()
+}
+
+#[test]
+fn vec_into_repr_c_releases_converted_elements() {
+    assert_no_leak(|| {
+        let v = vec!["a".to_owned(), "b".to_owned(), "c\0".to_owned(), "d".to_owned()];
+        assert!(v.into_repr_c().is_err());
+    });
+}
+
+#[test]
+fn struct_into_repr_c_releases_converted_fields() {
+    assert_no_leak(|| assert!(two("d\0").into_repr_c().is_err()));
+    assert_no_leak(|| {
+        let mut two = two("d");
+        two.c.push(one("c\0"));
+        assert!(two.into_repr_c().is_err());
+    });
+}
+
+#[test]
+fn enum_into_repr_c_releases_converted_fields() {
+    assert_no_leak(|| {
+        let message = Message::Text {
+            text: "c".to_owned(),
+            bytes: vec![1, 2, 3],
+            words: vec!["a".to_owned(), "b\0".to_owned()],
+        };
+        assert!(message.into_repr_c().is_err());
+    });
+}
+
+#[test]
+fn vec_from_repr_c_owned_releases_every_element() {
+    assert_no_leak(|| {
+        let elts = vec![
+            CString::new("a").unwrap().into_raw(),
+            invalid_utf8(),
+            CString::new("c").unwrap().into_raw(),
+        ];
+        let mut v_ffi = FfiVec::from_vec(elts);
+        assert!(unsafe { Vec::<String>::from_repr_c_owned(&mut v_ffi) }.is_err());
+    });
+}
+
+#[test]
+fn struct_from_repr_c_owned_releases_every_field() {
+    assert_no_leak(|| {
+        let mut two_ffi = two("d").into_repr_c().unwrap();
+        unsafe { drop(CString::from_raw(two_ffi.a)) };
+        two_ffi.a = invalid_utf8();
+        assert!(unsafe { Two::from_repr_c_owned(&mut two_ffi) }.is_err());
+    });
+    assert_no_leak(|| {
+        let mut two_ffi = two("d").into_repr_c().unwrap();
+        unsafe { drop(CString::from_raw(two_ffi.d.a)) };
+        two_ffi.d.a = invalid_utf8();
+        assert!(unsafe { Two::from_repr_c_owned(&mut two_ffi) }.is_err());
+    });
+}
+
+#[test]
+fn enum_from_repr_c_owned_releases_every_field() {
+    assert_no_leak(|| {
+        let message = Message::Text {
+            text: "c".to_owned(),
+            bytes: vec![1, 2, 3],
+            words: vec!["a".to_owned(), "b".to_owned()],
+        };
+        let mut message_ffi = message.into_repr_c().unwrap();
+        {
+            let payload = unsafe { &mut *message_ffi.payload.text };
+            unsafe { drop(CString::from_raw(payload.text)) };
+            payload.text = invalid_utf8();
+        }
+        assert!(unsafe { Message::from_repr_c_owned(&mut message_ffi) }.is_err());
+    });
+}
+
+#[test]
+fn owned_ffi_releases_exactly_once() {
+    assert_no_leak(|| drop(OwnedFfi::new(two("d")).unwrap()));
+    assert_no_leak(|| {
+        let ptr = OwnedFfi::new(two("d")).unwrap().into_raw();
+        drop(unsafe { OwnedFfi::<Two>::from_raw(ptr) });
+    });
+    assert_no_leak(|| {
+        let two_owned = OwnedFfi::new(two("d")).unwrap();
+        assert_eq!(two_owned.into_rust().unwrap().d.a, "d");
+    });
+}
+
+#[test]
+fn output_only_types_are_released_through_release_c() {
+    assert_no_leak(|| {
+        let reports = vec![Report("a".to_owned()), Report("b\0".to_owned())];
+        assert!(reports.into_repr_c().is_err());
+    });
+    assert_no_leak(|| {
+        let reports = vec![(Report("a".to_owned()), 1u32), (Report("b".to_owned()), 2)];
+        drop(OwnedFfi::new(reports).unwrap());
+    });
+}
+
+#[test]
+fn values_deeper_than_the_limit_are_released_whole() {
+    let len = DEFAULT_MAX_DEPTH + 72;
+    assert_no_leak(|| drop(OwnedFfi::new(chain(len)).unwrap()));
+    assert_no_leak(|| {
+        let link_owned = OwnedFfi::new(chain(len)).unwrap();
+        assert!(link_owned.to_rust().is_err());
+        assert_eq!(link_owned.into_rust().unwrap(), chain(len));
+    });
+}
@@ -0,0 +1,46 @@
+extern crate ffi_trait_poc;
+
+use std::ffi::CString;
+
+use ffi_trait_poc::tuple::{FfiTuple2, TupleError3};
+use ffi_trait_poc::{ErrorCode, ErrorPath, FromReprC, Header, IntoReprC, OwnedFfi};
+
+#[test]
+fn pairs_and_twelve_element_tuples_round_trip() {
+    let pair = ("a".to_owned(), vec![1u8, 2, 3]);
+    let pair_owned = OwnedFfi::new(pair.clone()).unwrap();
+    assert_eq!(pair_owned._1.len, 3);
+    assert_eq!(pair_owned.view().unwrap(), ("a", &[1, 2, 3][..]));
+    assert_eq!(pair_owned.to_rust().unwrap(), pair);
+    assert_eq!(pair_owned.into_rust().unwrap(), pair);
+
+    let twelve = (1u8, 2u16, 3u32, 4u64, 5i8, 6i16, 7i32, 8i64, 9.0f32, 10.0f64, true, 'c');
+    assert_eq!(OwnedFfi::new(twelve).unwrap().into_rust().unwrap(), twelve);
+}
+
+#[test]
+fn errors_name_the_tuple_field() {
+    let e = (1u32, "a".to_owned(), "b\0".to_owned()).into_repr_c().unwrap_err();
+    assert!(matches!(e, TupleError3::Field2(_)));
+    assert_eq!(e.path().to_string(), "_2");
+    assert_eq!(e.error_code(), 2);
+
+    let mut pair_ffi = FfiTuple2 {
+        _0: CString::new("a").unwrap().into_raw(),
+        _1: unsafe { CString::from_vec_unchecked(vec![0xff]) }.into_raw(),
+    };
+    let e = unsafe { <(String, String)>::from_repr_c_owned(&mut pair_ffi) }.unwrap_err();
+    assert_eq!(e.path().to_string(), "_1");
+}
+
+#[test]
+fn tuple_structs_are_named_after_their_elements() {
+    let mut header = Header::new("TUPLE_H");
+    header.register::<Vec<(String, Vec<u8>)>>();
+    let header = header.render();
+    let bytes = header.find("struct FfiBytes {").unwrap();
+    let pair = header.find("struct FfiTuple2_String_FfiBytes {\n    char *_0;\n    FfiBytes _1;\n")
+                     .unwrap();
+    let vec = header.find("struct FfiVec_FfiTuple2_String_FfiBytes {").unwrap();
+    assert!(bytes < pair && pair < vec);
+}
@@ -0,0 +1,26 @@
+// C writes an empty vec as `{NULL, 0, 0}` rather than with a dangling pointer.
+
+extern crate ffi_trait_poc;
+
+use std::ptr;
+
+use ffi_trait_poc::{FfiVec, FromReprC, FromReprCBorrowed};
+
+fn null<T>() -> FfiVec<T> {
+    FfiVec {
+        ptr: ptr::null_mut(),
+        len: 0,
+        cap: 0,
+    }
+}
+
+#[test]
+fn null_pointers_are_empty_vecs() {
+    assert!(unsafe { Vec::<String>::from_repr_c_cloned(&null()) }.unwrap().is_empty());
+    assert!(unsafe { Vec::<String>::from_repr_c_borrowed(&null()) }.unwrap().is_empty());
+    assert!(unsafe { Vec::<String>::from_repr_c_owned(&mut null()) }.unwrap().is_empty());
+
+    assert!(unsafe { Vec::<u8>::from_repr_c_cloned(&null()) }.unwrap().is_empty());
+    assert!(unsafe { Vec::<u8>::from_repr_c_borrowed(&null()) }.unwrap().is_empty());
+    assert!(unsafe { Vec::<u8>::from_repr_c_owned(&mut null()) }.unwrap().is_empty());
+}